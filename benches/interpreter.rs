@@ -0,0 +1,65 @@
+//! Times the bundled programs through the naive interpreter, with `--optimize`,
+//! with `--optimize` on a sparse tape and with `--optimize` and the old `match` dispatch.
+//!
+//! It takes the best of a few runs with `Instant` itself rather than using
+//! Criterion, so the crate still has no dependencies, dev ones included.
+
+use mindsuck::{Config, Dispatch, Passes, Program};
+use std::time::{Duration, Instant};
+
+const PROGRAMS: [(&str, &str); 2] = [
+    ("busy", include_str!("../examples/busy.bf")),
+    ("bottles", include_str!("../examples/bottles.bf")),
+];
+const RUNS: usize = 5;
+
+fn run(source: &str, config: Config) -> Duration {
+    let mut prog = Program::with_config(config);
+    prog.compile(source).expect("benchmark programs compile");
+
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            prog.run_with_io(b"").expect("benchmark programs run");
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    for (name, source) in PROGRAMS {
+        let optimize = Config {
+            optimize: Passes::ALL,
+            ..Config::default()
+        };
+        let naive = run(source, Config::default());
+        let optimized = run(source, optimize.clone());
+        let sparse = run(
+            source,
+            Config {
+                sparse: true,
+                ..optimize.clone()
+            },
+        );
+        let matched = run(
+            source,
+            Config {
+                dispatch: Dispatch::Match,
+                ..optimize
+            },
+        );
+
+        println!(
+            "{:<8} naive {:>10.2?}  optimized {:>10.2?}  {:.2}x  sparse {:>10.2?}  {:.2}x  match {:>10.2?}  {:.2}x",
+            name,
+            naive,
+            optimized,
+            naive.as_secs_f64() / optimized.as_secs_f64(),
+            sparse,
+            optimized.as_secs_f64() / sparse.as_secs_f64(),
+            matched,
+            optimized.as_secs_f64() / matched.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Compares buffered and unbuffered output on a program that prints a megabyte of text.
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Prints 'A' a million times from six nested loops of ten.
+const SOURCE: &str =
+    "++++++++[>++++++++<-]>+>++++++++++[>++++++++++[>++++++++++[>++++++++++[>++++++++++[>++++++++++[<<<<<<.>>>>>>-]<-]<-]<-]<-]<-]";
+const BYTES: f64 = 1_000_000.0;
+const RUNS: usize = 5;
+
+fn run(path: &std::path::Path, args: &[&str]) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let status = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+                .args(args)
+                .arg(path)
+                .stdout(Stdio::null())
+                .status()
+                .expect("failed to run mindsuck");
+
+            assert!(status.success());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let path = std::env::temp_dir().join("mindsuck-bench-output.bf");
+    std::fs::write(&path, SOURCE).expect("failed to write the benchmark program");
+
+    for (name, args) in [("buffered", &[][..]), ("unbuffered", &["--unbuffered"][..])] {
+        let elapsed = run(&path, args);
+
+        println!(
+            "{:<12} {:>10.2?} {:>8.2} MB/s",
+            name,
+            elapsed,
+            BYTES / elapsed.as_secs_f64() / 1_000_000.0
+        );
+    }
+
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,15 @@
+Busy work for benchmarking
+Three nested loops count down from two hundred each while the innermost one
+keeps adding to two cells and at the end it prints ok
+
+++++++++++[>++++++++++++++++++++<-]>
+[
+    >++++++++++[>++++++++++++++++++++<-]>
+    [
+        >++++++++++[>++++++++++++++++++++<-]>
+        [>+>+++<<-]
+        <<-
+    ]
+    <<-
+]
+>>>>>>>++++++++++[>+++++++++++<-]>+.----.>++++++++++.
@@ -0,0 +1,85 @@
+A brainfuck interpreter in brainfuck
+Give it a program then an exclamation mark then whatever input the program wants
+
+The program is kept as codes from 1 to 8 (plus comma minus dot left right open close)
+with a gap of five empty cells in front of the instruction about to run: everything left
+of the gap has run and everything right of it is still to come
+The data tape starts three cells after the end of the program and takes three cells for
+each of its own: a marker that is 1 left of the data pointer and 0 from it on; the value;
+and a spare; so the pointer is found by walking right over the markers and the gap again
+by walking left over them and then the rest of the program
+
+>>>>>                                   leave room for the gap in front of the first instruction
+,[                                      read the program until the exclamation mark or the end of the input
+    >+<                                 set the flag for the switch on the character
+    ---------------------------------[  take off 33 and so on: not the exclamation mark
+    ----------[                         not plus
+    -[-[-[                              not comma or minus or dot
+    --------------[--[                  not left or right
+    -----------------------------[      not open
+    --[[-]>->>+<<<]                     not close either so it's a comment: just flag the next read
+    >[->++++++++>>+<<<]<                close is 8: keep it and flag the next read
+    ]>[->+++++++>>+<<<]<                open is 7
+    ]>[->++++++>>+<<<]<                 right is 6
+    ]>[->+++++>>+<<<]<                  left is 5
+    ]>[->++++>>+<<<]<                   dot is 4
+    ]>[->+++>>+<<<]<                    minus is 3
+    ]>[->++>>+<<<]<                     comma is 2
+    ]>[->+>>+<<<]<                      plus is 1
+    ]>[-]<                              the exclamation mark ends the program
+    >>[-<<+>>]>[-<<+>>]>[-<<+>>]<<<<    move the code and the flag back next to the character
+    [>]                                 step past a code but not a comment
+    >[-<,>]<                            read the next character unless that was the end
+]
+<[<]>                                   back to the first instruction
+
+[                                       run instructions until the empty cell after the last one
+    [-<+<+>>]<<[->>+<<]>>               copy the instruction into the end of the gap
+    <<+>                                set the flag for the switch on it
+    -[-[-[-[-[-[-[                      not plus or comma or minus or dot or left or right or open
+    -<[                                 close
+        >>[>]>>>[>>>]                   find the data pointer
+        >>+<[>-]>[<<                    if the cell is zero
+            <<<[<<<]<[<]<-              go back and clear the flag
+            >>[>]>>>[>>>]>>->           and come back
+        ]<<<
+        <<<[<<<]<[<]<                   back to the gap where the flag is now the nesting depth
+        [                               go back until the close it started from is matched
+            <<<<[->>>>>+<<<<<]          move the last instruction that ran to the near side of the gap
+            >>>>[-<+>]>                 and the depth along with it
+            [-<<<+<+>>>>]<<<[->>>+<<<]< copy the instruction further into the gap
+            <+>                         and switch on it
+            -[-[-[-[-[-[-[
+            -<[>>>+<<<-]>               one deeper on close
+            ]<[>>>-<<<-]>               one shallower on open
+            ]<[-]>]<[-]>]<[-]>]<[-]>]<[-]>]<[-]>
+            >>
+        ]
+    ]>
+    ]<[                                 open
+        >>[>]>>>[>>>]                   find the data pointer
+        >>+<[                           if the cell is not zero
+            <<<<[<<<]<[<]<-             go back and clear the flag
+            >>[>]>>>[>>>]>>-            and come back
+        ]>[->]<<<
+        <<<[<<<]<[<]<                   back to the gap where the flag is now the nesting depth
+        [                               go forward until the open it started from is matched
+            >>[-<<<<<+>>>>>]            move the instruction to the far side of the gap
+            <<[->+<]>>>                 and the depth along with it
+            [-<<<+<+>>>>]<<<[->>>+<<<]< copy the instruction further into the gap
+            <+>                         and switch on it
+            -[-[-[-[-[-[-[
+            -<[>>>-<<<-]>               one shallower on close
+            ]<[>>>+<<<-]>               one deeper on open
+            ]<[-]>]<[-]>]<[-]>]<[-]>]<[-]>]<[-]>
+            >>
+        ]
+    ]>
+    ]<[>>[>]>>>[>>>]+>>><<<[<<<]<[<]<-]>   right: mark the cell and move on to the next
+    ]<[>>[>]>>>[>>>]<<<-<<<[<<<]<[<]<-]>   left: unmark the cell to the left
+    ]<[>>[>]>>>[>>>]>.<<<<[<<<]<[<]<-]>    dot
+    ]<[>>[>]>>>[>>>]>-<<<<[<<<]<[<]<-]>    minus
+    ]<[>>[>]>>>[>>>]>,<<<<[<<<]<[<]<-]>    comma
+    ]<[>>[>]>>>[>>>]>+<<<<[<<<]<[<]<-]>    plus
+    >[-<<<<<+>>>>>]>                    move the instruction to the far side of the gap
+]
@@ -0,0 +1,29 @@
+ROT13 every character of the input until it runs out
+Needs the EOF cell to be minus one or left alone: run it with eof minusone or unchanged
+
+-,+[                        read the first character and start the character loop
+    -[                      skip ahead if the character is zero
+        >>++++[>++++++++<-] set up the divisor (32) for the division loop
+        <+<-[               set up the dividend (x minus 1) and divide
+            >+>+>-[>>>]     bump copy and remainder and lower the divisor (normal case skips on)
+            <[[>+<-]>>+>]   special case: move the remainder back to the divisor and bump the quotient
+            <<<<<-          lower the dividend
+        ]                   end of the division loop
+    ]>>>[-]+                end of the skip; clear the old divisor and reuse it as a flag
+    >--[-[<->+++[-]]]<[     clear the flag unless the quotient was 2 or 3; clear the quotient; check the flag
+        ++++++++++++<[      if the flag is set then set up the divisor (13) for the second division
+            >-[>+>>]        lower the divisor (normal case bumps the remainder)
+            >[+[<+>-]>+>>]  special case: bump the remainder and move it back to the divisor and bump the quotient
+            <<<<<-          lower the dividend
+        ]                   end of the division loop
+        >>[<+>-]            add the remainder back to the divisor to get 13 again
+        >[                  skip ahead if the quotient was 0
+            -[              lower the quotient and skip ahead if it was 1
+                -<<[-]>>    clear the quotient and divisor if it was 2
+            ]<<[<<->>-]>>   clear the divisor and take 13 off the copy if it was 1
+        ]<<[<<+>>-]         clear the divisor and add 13 to the copy if it was 0
+    ]                       end of the outer skip (lands here if the character was not a letter)
+    <[-]                    clear the remainder from the first division if the second was skipped
+    <.[-]                   write the ROT13ed copy and clear it
+    <-,+                    read the next character
+]                           end of the character loop
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mindsuck::{Config, Passes, Program};
+
+// Compiling any source has to come back as Ok or a CompileError, never a panic.
+fuzz_target!(|data: &[u8]| {
+    let source = String::from_utf8_lossy(data);
+
+    for optimize in [Passes::NONE, Passes::ALL] {
+        let mut prog = Program::with_config(Config {
+            optimize,
+            debug_ops: true,
+            ..Config::default()
+        });
+
+        let compiled = prog.compile(&source);
+
+        // Validating only checks brackets, so it has to agree whenever those are the problem.
+        if Program::validate(&source).is_err() {
+            assert!(compiled.is_err());
+        }
+    }
+});
@@ -0,0 +1,66 @@
+use core::cmp::Ordering;
+
+/// A test on one cell for [`Config::break_when`](crate::Config::break_when),
+/// written like `cell0==42` or `cell3 > 9`.
+///
+/// The cell is compared the way the interpreter shows it, so with
+/// [`Config::signed`](crate::Config::signed) `cell0<0` means what it says.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Condition {
+    pub cell: usize,
+    pub comparison: Comparison,
+    pub value: i64,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Condition {
+    /// Reads a condition from `cellN`, one of `==`, `!=`, `<`, `<=`, `>` or
+    /// `>=`, and a number.
+    pub fn parse(text: &str) -> Option<Condition> {
+        let rest = text.trim().strip_prefix("cell")?;
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let cell = rest[..digits].parse().ok()?;
+        let rest = rest[digits..].trim_start();
+
+        // The two character operators have to be tried before `<` and `>`.
+        let (comparison, value) = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ]
+        .into_iter()
+        .find_map(|(symbol, comparison)| Some((comparison, rest.strip_prefix(symbol)?)))?;
+
+        Some(Condition {
+            cell,
+            comparison,
+            value: value.trim().parse().ok()?,
+        })
+    }
+
+    /// Whether the condition is true of a cell showing `value`.
+    pub fn holds(&self, value: i64) -> bool {
+        let ordering = value.cmp(&self.value);
+
+        match self.comparison {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! The `.bfc` files [`Program::save_compiled`] writes.
+//!
+//! A file is the magic bytes `BFC\0`, a version byte, the number of
+//! instructions as a little-endian `u64` and then every instruction as its
+//! operator's index in [`Op::ALL`] followed by its operand, again as a
+//! little-endian `u64`. The End is left out.
+
+use crate::embedded::Stack;
+use crate::{Instruction, Op, Program};
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"BFC\0";
+const VERSION: u8 = 1;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+
+    input.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+impl Program {
+    /// Writes the compiled program out as a `.bfc` file, for
+    /// [`Program::load_compiled`] to run later without compiling it again.
+    ///
+    /// Only the instructions are saved. What changes how a program compiles,
+    /// like [`Config::optimize`](crate::Config::optimize) and
+    /// [`Config::debug_ops`](crate::Config::debug_ops), is baked into them,
+    /// everything else comes from the config that loads the file. A SetConst
+    /// is saved already wrapped to the cell width it was compiled for.
+    pub fn save_compiled(&self, out: &mut impl Write) -> io::Result<()> {
+        let program = self.instructions();
+        let mask = self.config.cell_width.mask() as usize;
+
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&(program.len() as u64).to_le_bytes())?;
+
+        for instruction in program {
+            let operand = match instruction.operator {
+                Op::SetConst => instruction.operand & mask,
+                _ => instruction.operand,
+            };
+
+            out.write_all(&[instruction.operator as u8])?;
+            out.write_all(&(operand as u64).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the compiled program with one from [`Program::save_compiled`].
+    ///
+    /// Anything that isn't a `.bfc` file of this version, is longer than
+    /// [`Config::program_size`](crate::Config::program_size), has brackets that
+    /// don't jump to their match or has an operand compiling never gives, like
+    /// a move of 0 cells or a SetConst too big for the cell, fails with
+    /// [`io::ErrorKind::InvalidData`], and leaves the program as it was.
+    pub fn load_compiled(&mut self, input: &mut impl Read) -> io::Result<()> {
+        let mut header = [0; 5];
+
+        input.read_exact(&mut header)?;
+
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a compiled brainfuck program".to_owned()));
+        }
+
+        if header[4] != VERSION {
+            return Err(invalid(format!("unsupported .bfc version {}", header[4])));
+        }
+
+        let len = usize::try_from(read_u64(input)?)
+            .ok()
+            .filter(|&len| len <= self.config.program_size)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "program is longer than {} instructions",
+                    self.config.program_size
+                ))
+            })?;
+        let mask = self.config.cell_width.mask() as usize;
+        // The length is only a claim until the instructions are actually there.
+        let mut program = Vec::with_capacity(len.min(1 << 16));
+
+        for pc in 0..len {
+            let mut operator = [0];
+
+            input.read_exact(&mut operator)?;
+
+            let operator = Op::ALL
+                .get(usize::from(operator[0]))
+                .copied()
+                .filter(|&operator| operator != Op::End)
+                .ok_or_else(|| invalid(format!("unknown operator at instruction {}", pc)))?;
+            let operand = usize::try_from(read_u64(input)?)
+                .map_err(|_| invalid(format!("operand too big at instruction {}", pc)))?;
+            // A scan that moves 0 cells would never get off the cell it started on.
+            let valid = match operator {
+                Op::IncDp | Op::DecDp | Op::IncVal | Op::DecVal | Op::ScanRight | Op::ScanLeft => {
+                    operand > 0
+                }
+                Op::SetConst => operand <= mask,
+                _ => true,
+            };
+
+            if !valid {
+                return Err(invalid(format!(
+                    "invalid operand {} at instruction {}",
+                    operand, pc
+                )));
+            }
+
+            program.push(Instruction { operator, operand });
+        }
+
+        // A bad jump would send `pc` anywhere, so the brackets get checked the
+        // same way compiling matches them up.
+        let mut open = Stack::with_limit(Vec::new(), len);
+
+        for (pc, instruction) in program.iter().enumerate() {
+            let matched = match instruction.operator {
+                Op::JmpFwd => open.push(pc).is_ok(),
+                Op::JmpBck => open.pop().is_ok_and(|start| {
+                    instruction.operand == start && program[start].operand == pc
+                }),
+                _ => true,
+            };
+
+            if !matched {
+                return Err(invalid(format!("unmatched bracket at instruction {}", pc)));
+            }
+        }
+
+        if let Ok(pc) = open.pop() {
+            return Err(invalid(format!("unmatched bracket at instruction {}", pc)));
+        }
+
+        program.push(Instruction::default());
+        self.instructions = program;
+        self.pc = 0;
+        self.depth = 0;
+
+        Ok(())
+    }
+}
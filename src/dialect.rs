@@ -0,0 +1,68 @@
+use crate::{source_positions, CompileError, Dialect};
+
+impl Dialect {
+    /// Translates `source` from this dialect into plain brainf**k.
+    ///
+    /// Every instruction lands where its first token was and everything else
+    /// is blanked out, newlines aside, so positions in compile errors still
+    /// point into the original source.
+    pub fn translate(self, source: &str) -> Result<String, CompileError> {
+        let word = match self {
+            Dialect::Brainfuck => return Ok(source.to_owned()),
+            Dialect::Ook => "Ook",
+            Dialect::ShortOok => "",
+        };
+
+        let chars = source_positions(source).collect::<Vec<_>>();
+        let mut translated = chars
+            .iter()
+            .map(|&(_, _, c)| if c == '\n' { '\n' } else { ' ' })
+            .collect::<Vec<_>>();
+        let malformed = |at: usize| {
+            let (line, column, _) = chars[at];
+            CompileError::MalformedOok { line, column }
+        };
+
+        // Each token is the index it starts at and its punctuation.
+        let mut tokens = Vec::new();
+        let mut at = 0;
+
+        while at < chars.len() {
+            if chars[at].2.is_whitespace() {
+                at += 1;
+                continue;
+            }
+
+            let matches_word = word
+                .chars()
+                .enumerate()
+                .all(|(i, c)| chars.get(at + i).map(|&(_, _, ch)| ch) == Some(c));
+
+            match chars.get(at + word.len()).map(|&(_, _, c)| c) {
+                Some(mark @ ('.' | '?' | '!')) if matches_word => tokens.push((at, mark)),
+                _ => return Err(malformed(at)),
+            }
+
+            at += word.len() + 1;
+        }
+
+        for pair in tokens.chunks(2) {
+            let op = match pair {
+                [(_, '.'), (_, '?')] => '>',
+                [(_, '?'), (_, '.')] => '<',
+                [(_, '.'), (_, '.')] => '+',
+                [(_, '!'), (_, '!')] => '-',
+                [(_, '!'), (_, '.')] => '.',
+                [(_, '.'), (_, '!')] => ',',
+                [(_, '!'), (_, '?')] => '[',
+                [(_, '?'), (_, '!')] => ']',
+                // A pair that means nothing, or a last token without a partner.
+                _ => return Err(malformed(pair[0].0)),
+            };
+
+            translated[pair[0].0] = op;
+        }
+
+        Ok(translated.into_iter().collect())
+    }
+}
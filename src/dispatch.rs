@@ -0,0 +1,314 @@
+//! The table driven interpreter behind [`Dispatch::Table`].
+//!
+//! Every operator gets a handler function and the loop calls through an array
+//! of them indexed by the operator, instead of going through the big `match`
+//! in `Program::interpret`. It only covers plain runs: tracing, the debugger
+//! and profiling always take the `match`.
+
+use crate::{
+    check_limits, move_left, move_right, read_cell, read_input, write_cell, Config, EofMode,
+    Instruction, InterpreterError, Op, RunStats, Tape,
+};
+use std::io::{Read, Write};
+use std::time::Instant;
+
+/// Everything a handler can touch.
+struct Machine<'a> {
+    instructions: &'a [Instruction],
+    data: &'a mut Tape,
+    ptr: usize,
+    pc: usize,
+    config: &'a Config,
+    mask: u32,
+    input: &'a mut dyn Read,
+    out: &'a mut dyn Write,
+    output_bytes: u64,
+    depth: usize,
+    max_loop_depth: usize,
+    steps: u64,
+    max_steps: u64,
+    deadline: Option<Instant>,
+}
+
+impl Machine<'_> {
+    /// Counts one more step for an instruction that takes more than one, like a scan.
+    fn step(&mut self) -> Result<(), InterpreterError> {
+        check_limits(self.steps, self.max_steps, self.deadline, self.config)?;
+        self.steps += 1;
+
+        Ok(())
+    }
+
+    /// A loop optimizing folded away would still have been entered, so it
+    /// counts for [`Config::max_loop_depth`] the same as the loop would.
+    fn enter_folded_loop(&self) -> Result<(), InterpreterError> {
+        match self.depth == self.max_loop_depth && self.cell()? != 0 {
+            true => Err(InterpreterError::LoopDepthExceeded { pc: self.pc }),
+            false => Ok(()),
+        }
+    }
+
+    fn cell(&self) -> Result<u32, InterpreterError> {
+        read_cell(self.data, self.ptr, self.pc)
+    }
+
+    fn set_cell(&mut self, value: u32) -> Result<(), InterpreterError> {
+        write_cell(self.data, self.ptr, self.pc, value)
+    }
+}
+
+type Handler = fn(&mut Machine, usize) -> Result<(), InterpreterError>;
+
+/// Indexed by `Op as usize`, so it has to stay in the same order as [`Op::ALL`].
+const HANDLERS: [Handler; 15] = [
+    end, inc_dp, dec_dp, inc_val, dec_val, out, input, jmp_fwd, jmp_bck, clear, scan_right,
+    scan_left, dump, end, set_const,
+];
+
+/// For End and Halt, which the loop stops before.
+fn end(machine: &mut Machine, _: usize) -> Result<(), InterpreterError> {
+    unreachable!("the loop stops before End and Halt at {}", machine.pc)
+}
+
+fn inc_dp(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    machine.ptr = move_right(machine.data, machine.ptr, operand, machine.config).ok_or(
+        InterpreterError::PointerOutOfBounds {
+            pc: machine.pc,
+            ptr: machine.ptr,
+        },
+    )?;
+
+    Ok(())
+}
+
+fn dec_dp(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    machine.ptr = move_left(machine.data, machine.ptr, operand, machine.config).ok_or(
+        InterpreterError::PointerOutOfBounds {
+            pc: machine.pc,
+            ptr: machine.ptr,
+        },
+    )?;
+
+    Ok(())
+}
+
+fn inc_val(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    let value = machine.cell()?.wrapping_add(operand as u32) & machine.mask;
+
+    machine.set_cell(value)
+}
+
+fn dec_val(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    let value = machine.cell()?.wrapping_sub(operand as u32) & machine.mask;
+
+    machine.set_cell(value)
+}
+
+fn out(machine: &mut Machine, _: usize) -> Result<(), InterpreterError> {
+    let failed = |_| InterpreterError::FailedToWrite {
+        pc: Some(machine.pc),
+    };
+
+    let (bytes, len) = machine.config.output_bytes(machine.cell()?);
+
+    if machine.config.over_output_limit(machine.output_bytes, len) {
+        return Err(InterpreterError::OutputLimitExceeded {
+            pc: machine.pc,
+            bytes: machine.output_bytes,
+        });
+    }
+
+    machine.out.write_all(&bytes[..len]).map_err(failed)?;
+    machine.output_bytes += len as u64;
+
+    if machine.config.unbuffered {
+        machine.out.flush().map_err(failed)?;
+    }
+
+    Ok(())
+}
+
+fn input(machine: &mut Machine, _: usize) -> Result<(), InterpreterError> {
+    machine
+        .out
+        .flush()
+        .map_err(|_| InterpreterError::FailedToWrite {
+            pc: Some(machine.pc),
+        })?;
+
+    let value = match read_input(machine.input, machine.config) {
+        Ok(Some(value)) => value,
+        Ok(None) => match machine.config.eof {
+            EofMode::Zero => 0,
+            EofMode::NegativeOne => machine.mask,
+            EofMode::Unchanged => machine.cell()?,
+        },
+        Err(()) => return Err(InterpreterError::FailedToRead { pc: machine.pc }),
+    };
+
+    machine.set_cell(value)
+}
+
+fn jmp_fwd(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    if machine.cell()? == 0 {
+        machine.pc = operand;
+    } else if machine.depth == machine.max_loop_depth {
+        return Err(InterpreterError::LoopDepthExceeded { pc: machine.pc });
+    } else {
+        machine.depth += 1;
+    }
+
+    Ok(())
+}
+
+fn jmp_bck(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    if machine.cell()? != 0 {
+        machine.pc = operand;
+    } else {
+        machine.depth -= 1;
+    }
+
+    Ok(())
+}
+
+fn clear(machine: &mut Machine, _: usize) -> Result<(), InterpreterError> {
+    machine.set_cell(0)
+}
+
+fn set_const(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    machine.set_cell(operand as u32 & machine.mask)
+}
+
+fn scan_right(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    let mut first = true;
+
+    while machine.cell()? != 0 {
+        if !first {
+            machine.step()?;
+        }
+
+        first = false;
+
+        if operand == 1 {
+            machine.ptr = machine.data.next_zero(machine.ptr) - 1;
+        }
+
+        inc_dp(machine, operand)?;
+    }
+
+    Ok(())
+}
+
+fn scan_left(machine: &mut Machine, operand: usize) -> Result<(), InterpreterError> {
+    let mut first = true;
+
+    while machine.cell()? != 0 {
+        if !first {
+            machine.step()?;
+        }
+
+        first = false;
+
+        if operand == 1 {
+            machine.ptr = machine
+                .data
+                .previous_zero(machine.ptr)
+                .map_or(0, |zero| zero + 1);
+        }
+
+        dec_dp(machine, operand)?;
+    }
+
+    Ok(())
+}
+
+fn dump(machine: &mut Machine, _: usize) -> Result<(), InterpreterError> {
+    machine
+        .out
+        .flush()
+        .map_err(|_| InterpreterError::FailedToWrite {
+            pc: Some(machine.pc),
+        })?;
+    eprintln!(
+        "# pc {:04} | {}",
+        machine.pc,
+        crate::tape_window(machine.data, machine.ptr, 8, machine.config)
+    );
+
+    Ok(())
+}
+
+/// Runs `instructions` from `pc` with the pointer at `ptr` and `depth` loops
+/// deep, leaving all three where the run stopped, the same as the `match` does.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn interpret(
+    instructions: &[Instruction],
+    data: &mut Tape,
+    ptr: &mut usize,
+    pc: &mut usize,
+    depth: &mut usize,
+    max_steps: u64,
+    config: &Config,
+    input: &mut dyn Read,
+    out: &mut dyn Write,
+) -> Result<RunStats, InterpreterError> {
+    let mut machine = Machine {
+        instructions,
+        data,
+        ptr: *ptr,
+        pc: *pc,
+        config,
+        mask: config.cell_width.mask(),
+        input,
+        out,
+        output_bytes: 0,
+        depth: *depth,
+        max_loop_depth: config.max_loop_depth.unwrap_or(usize::MAX),
+        steps: 0,
+        max_steps,
+        deadline: config.timeout.map(|timeout| Instant::now() + timeout),
+    };
+    let mut max_ptr = machine.ptr;
+    let mut max_value = machine.cell()?;
+    let mut result = Ok(());
+
+    while !machine.instructions[machine.pc].operator.stops() {
+        if let Err(error) = machine.step() {
+            result = Err(error);
+            break;
+        }
+
+        let Instruction { operator, operand } = machine.instructions[machine.pc];
+
+        let handled = match operator.folds_loop() {
+            true => machine.enter_folded_loop(),
+            false => Ok(()),
+        }
+        .and_then(|_| HANDLERS[operator as usize](&mut machine, operand));
+
+        let cell = match handled.and_then(|_| machine.cell()) {
+            Ok(cell) => cell,
+            Err(error) => {
+                result = Err(error);
+                break;
+            }
+        };
+
+        max_ptr = max_ptr.max(machine.ptr);
+        max_value = max_value.max(cell);
+        machine.pc += 1;
+    }
+
+    *ptr = machine.ptr;
+    *pc = machine.pc;
+    *depth = machine.depth;
+
+    result.map(|_| RunStats {
+        steps: machine.steps,
+        output_bytes: machine.output_bytes,
+        final_ptr: machine.ptr,
+        max_ptr,
+        max_value,
+        halted: machine.instructions[machine.pc].operator == Op::Halt,
+    })
+}
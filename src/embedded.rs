@@ -0,0 +1,647 @@
+//! The compiler and a stripped down interpreter that only need `core`, for
+//! running programs on microcontrollers and anywhere else without `std`.
+//!
+//! A [`Machine`] keeps its program, tape and bracket stack in fixed arrays
+//! sized by its const parameters, and does its I/O through the [`Input`] and
+//! [`Output`] traits. It understands the same [`Config`] as [`Program`](crate::Program)
+//! for the cell width, EOF, pointer policy, step limit, interrupt and compile
+//! options, the size options, the timeout, the output limit and anything that
+//! prints to stderr are left out.
+
+use crate::{
+    source_positions, strip_bom, CompileError, Config, EofMode, InterpreterError, PointerPolicy,
+};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Op {
+    End,
+    IncDp,
+    DecDp,
+    IncVal,
+    DecVal,
+    Out,
+    In,
+    JmpFwd,
+    JmpBck,
+    Clear,
+    ScanRight,
+    ScanLeft,
+    Dump,
+    Halt,
+    SetConst,
+}
+
+impl Op {
+    pub const ALL: [Op; 15] = [
+        Op::End,
+        Op::IncDp,
+        Op::DecDp,
+        Op::IncVal,
+        Op::DecVal,
+        Op::Out,
+        Op::In,
+        Op::JmpFwd,
+        Op::JmpBck,
+        Op::Clear,
+        Op::ScanRight,
+        Op::ScanLeft,
+        Op::Dump,
+        Op::Halt,
+        Op::SetConst,
+    ];
+
+    /// The operator a single character of source stands for, if it is one.
+    ///
+    /// Only the eight plain instructions, `#` and `@` map to anything, the rest
+    /// (like [`Op::Clear`]) only ever come out of optimizing. `#` is
+    /// [`Op::Dump`] and `@` is [`Op::Halt`] here even though compiling only
+    /// treats them as such with [`Config::debug_ops`].
+    pub fn from_char(c: char) -> Option<Op> {
+        match c {
+            '>' => Some(Op::IncDp),
+            '<' => Some(Op::DecDp),
+            '+' => Some(Op::IncVal),
+            '-' => Some(Op::DecVal),
+            '.' => Some(Op::Out),
+            ',' => Some(Op::In),
+            '[' => Some(Op::JmpFwd),
+            ']' => Some(Op::JmpBck),
+            '#' => Some(Op::Dump),
+            '@' => Some(Op::Halt),
+            _ => None,
+        }
+    }
+
+    /// Whether running stops at this operator, at the end or at an `@`.
+    pub(crate) fn stops(self) -> bool {
+        matches!(self, Op::End | Op::Halt)
+    }
+
+    /// Whether optimizing folded a whole loop into this operator.
+    #[cfg(feature = "std")]
+    pub(crate) fn folds_loop(self) -> bool {
+        matches!(
+            self,
+            Op::Clear | Op::ScanRight | Op::ScanLeft | Op::SetConst
+        )
+    }
+}
+
+/// Every instruction in `source` along with the byte index it is at, skipping
+/// comments the same way compiling does.
+pub fn tokens(source: &str) -> impl Iterator<Item = (usize, Op)> + '_ {
+    source
+        .char_indices()
+        .filter_map(|(index, c)| Op::from_char(c).map(|op| (index, op)))
+}
+
+#[derive(Debug)]
+pub(crate) enum StackErrors {
+    OverFlow,
+    UnderFlow,
+}
+
+/// Somewhere a [`Stack`] can keep what is pushed on it.
+pub(crate) trait Slots: AsRef<[Self::Item]> + AsMut<[Self::Item]> {
+    type Item: Copy;
+
+    /// Makes room for more items without going past `limit` in all,
+    /// returning whether there is any more room now.
+    fn grow(&mut self, _limit: usize) -> bool {
+        false
+    }
+}
+
+impl<T: Copy, const N: usize> Slots for [T; N] {
+    type Item = T;
+}
+
+#[cfg(feature = "std")]
+impl<T: Copy + Default> Slots for Vec<T> {
+    type Item = T;
+
+    fn grow(&mut self, limit: usize) -> bool {
+        let len = (self.len() * 2).max(64).min(limit);
+
+        if len <= self.len() {
+            return false;
+        }
+
+        self.resize(len, T::default());
+
+        true
+    }
+}
+
+/// A stack of whatever `arr` holds, kept in a `Vec` that grows as it gets
+/// deeper with `std` or a plain array without, that fails rather than going
+/// past its limit or popping when empty. Compiling keeps the brackets still
+/// open on one.
+pub(crate) struct Stack<A> {
+    ptr: usize,
+    arr: A,
+    limit: usize,
+}
+
+impl<A: Slots> Stack<A> {
+    /// A stack that holds exactly as many items as `arr` has room for.
+    pub fn new(arr: A) -> Stack<A> {
+        let limit = arr.as_ref().len();
+
+        Stack { ptr: 0, arr, limit }
+    }
+
+    /// A stack that starts out in `arr` and grows it as needed, up to `limit`
+    /// items.
+    #[cfg(feature = "std")]
+    pub fn with_limit(arr: A, limit: usize) -> Stack<A> {
+        Stack { ptr: 0, arr, limit }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.limit
+    }
+
+    pub fn push(&mut self, a: A::Item) -> Result<(), StackErrors> {
+        if self.ptr >= self.capacity() {
+            return Err(StackErrors::OverFlow);
+        }
+
+        if self.ptr >= self.arr.as_ref().len() && !self.arr.grow(self.limit) {
+            return Err(StackErrors::OverFlow);
+        }
+
+        self.arr.as_mut()[self.ptr] = a;
+        self.ptr += 1;
+        // self.ptr = self.ptr.wrapping_add(1);
+
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<A::Item, StackErrors> {
+        if self.ptr == 0 {
+            return Err(StackErrors::UnderFlow);
+        }
+
+        self.ptr -= 1;
+        // self.ptr = self.ptr.wrapping_sub(1);
+
+        Ok(self.arr.as_ref()[self.ptr])
+    }
+}
+
+/// A single compiled instruction.
+///
+/// Brackets form the jump table: a `JmpFwd` holds the index of its matching
+/// `JmpBck` and the `JmpBck` holds the index of its `JmpFwd`, both filled in by
+/// `compile` so `execute` never has to search for a match. Jumping lands on
+/// the bracket itself and the usual `pc` increment steps past it. A `SetConst`
+/// holds the value it sets and every other operator uses the operand as a
+/// repeat count.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Instruction {
+    pub operator: Op,
+    pub operand: usize,
+}
+
+impl Instruction {
+    fn new(operator: Op) -> Instruction {
+        Instruction {
+            operator,
+            operand: 0,
+        }
+    }
+}
+
+impl Default for Instruction {
+    fn default() -> Self {
+        Instruction::new(Op::End)
+    }
+}
+
+/// Compiles `source` into `instructions`, with loops nested no deeper than
+/// `stack` can hold.
+///
+/// The last slot of `instructions` is kept for the End, so programs can be at
+/// most `instructions.len() - 1` instructions long.
+pub(crate) fn compile<A: Slots<Item = usize>>(
+    source: &str,
+    instructions: &mut [Instruction],
+    stack: &mut Stack<A>,
+    config: &Config,
+) -> Result<(), CompileError> {
+    let program_size = instructions.len() - 1;
+    let stack_size = stack.capacity();
+    let mut pc: usize = 0;
+
+    // The `\r` of a CRLF line ending is whitespace like any other, so only a
+    // leading byte order mark needs taking care of.
+    for (line, column, c) in source_positions(strip_bom(source)) {
+        let idx = pc;
+
+        match c {
+            '>' | '<' | '+' | '-' => {
+                let operator = match c {
+                    '>' => Op::IncDp,
+                    '<' => Op::DecDp,
+                    '+' => Op::IncVal,
+                    _ => Op::DecVal,
+                };
+
+                match idx.checked_sub(1).map(|prev| &mut instructions[prev]) {
+                    Some(prev) if config.optimize.coalesce && prev.operator == operator => {
+                        prev.operand += 1;
+                        pc = pc.wrapping_sub(1);
+                    }
+                    // The value wraps like the cell would, `execute` masks it to the cell width.
+                    Some(prev)
+                        if config.optimize.set
+                            && matches!(prev.operator, Op::Clear | Op::SetConst)
+                            && matches!(operator, Op::IncVal | Op::DecVal) =>
+                    {
+                        prev.operand = match (prev.operator, operator) {
+                            (Op::Clear, Op::IncVal) => 1,
+                            (Op::Clear, _) => usize::MAX,
+                            (_, Op::IncVal) => prev.operand.wrapping_add(1),
+                            _ => prev.operand.wrapping_sub(1),
+                        };
+                        prev.operator = Op::SetConst;
+                        pc = pc.wrapping_sub(1);
+                    }
+                    _ => {
+                        instructions[idx].operator = operator;
+                        instructions[idx].operand = 1;
+                    }
+                }
+            }
+            // Nothing that follows has an operand, clear out whatever an earlier compile left.
+            '.' => instructions[idx] = Instruction::new(Op::Out),
+            ',' => instructions[idx] = Instruction::new(Op::In),
+            '#' if config.debug_ops => instructions[idx] = Instruction::new(Op::Dump),
+            '@' if config.debug_ops => instructions[idx] = Instruction::new(Op::Halt),
+            '[' => {
+                instructions[idx] = Instruction::new(Op::JmpFwd);
+
+                stack
+                    .push(pc)
+                    .map_err(|e| CompileError::from_stack(e, line, column, stack_size))?;
+            }
+            ']' => {
+                let jmp_pc: usize = stack
+                    .pop()
+                    .map_err(|e| CompileError::from_stack(e, line, column, stack_size))?;
+
+                let body = instructions[jmp_pc + 1];
+
+                if config.optimize.clear
+                    && idx == jmp_pc + 2
+                    && matches!(body.operator, Op::IncVal | Op::DecVal)
+                    && body.operand % 2 == 1
+                {
+                    // An odd step always reaches zero, so the loop just clears the cell.
+                    instructions[jmp_pc].operator = Op::Clear;
+                    pc = jmp_pc;
+                } else if config.optimize.scan
+                    && idx == jmp_pc + 2
+                    && matches!(body.operator, Op::IncDp | Op::DecDp)
+                {
+                    let operator = match body.operator {
+                        Op::IncDp => Op::ScanRight,
+                        _ => Op::ScanLeft,
+                    };
+
+                    instructions[jmp_pc].operator = operator;
+                    instructions[jmp_pc].operand = body.operand;
+                    pc = jmp_pc;
+                } else {
+                    instructions[idx].operator = Op::JmpBck;
+                    instructions[idx].operand = jmp_pc;
+                    instructions[jmp_pc].operand = pc;
+                }
+            }
+            ch if config.strict && !ch.is_whitespace() => {
+                return Err(CompileError::UnexpectedChar { ch, line, column })
+            }
+            _ => pc = pc.wrapping_sub(1),
+        }
+
+        pc = pc.wrapping_add(1);
+
+        if pc > program_size {
+            return Err(CompileError::ProgramTooLarge {
+                limit: program_size,
+            });
+        }
+    }
+
+    if stack.ptr > 0 {
+        return Err(unclosed_bracket(source, stack.ptr));
+    }
+
+    // The slot past the program size is only ever written by an instruction that
+    // fails the check above, so there is always room left for the End here.
+    instructions[pc].operator = Op::End;
+
+    Ok(())
+}
+
+/// Finds the innermost `[` left open in a program that ends `depth` loops deep.
+///
+/// Brackets only ever close, so that is the last `[` to open a loop at `depth`.
+fn unclosed_bracket(source: &str, depth: usize) -> CompileError {
+    let mut open = 0;
+    let mut position = (0, 0);
+
+    for (line, column, c) in source_positions(strip_bom(source)) {
+        match c {
+            '[' => {
+                open += 1;
+
+                if open == depth {
+                    position = (line, column);
+                }
+            }
+            ']' => open -= 1,
+            _ => (),
+        }
+    }
+
+    let (line, column) = position;
+    CompileError::UnclosedBracket { line, column }
+}
+
+/// Where `,` gets its bytes from.
+pub trait Input {
+    type Error;
+
+    /// The next byte, or `None` once the input has run out.
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error>;
+}
+
+/// Where `.` sends its bytes.
+pub trait Output {
+    type Error;
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Self::Error>;
+
+    /// Called before blocking on input and once the program is done.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Reads from the front of the slice, moving past every byte it hands out.
+impl Input for &[u8] {
+    type Error = core::convert::Infallible;
+
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error> {
+        match self.split_first() {
+            Some((&byte, rest)) => {
+                *self = rest;
+                Ok(Some(byte))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Fills the slice from the front, failing once it is full.
+impl Output for &mut [u8] {
+    type Error = ();
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Self::Error> {
+        match core::mem::take(self).split_first_mut() {
+            Some((first, rest)) => {
+                *first = byte;
+                *self = rest;
+                Ok(())
+            }
+            None => Err(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Input for std::io::Stdin {
+    type Error = std::io::Error;
+
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error> {
+        let mut byte = [0];
+
+        match std::io::Read::read_exact(self, &mut byte) {
+            Ok(()) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Output for std::io::Stdout {
+    type Error = std::io::Error;
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Self::Error> {
+        std::io::Write::write_all(self, &[byte])
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        std::io::Write::flush(self)
+    }
+}
+
+/// A program and its tape with everything in fixed arrays: room for
+/// `PROGRAM - 1` instructions, a tape of `TAPE` cells and loops nested up to
+/// `STACK` deep.
+pub struct Machine<const PROGRAM: usize, const TAPE: usize, const STACK: usize> {
+    config: Config,
+    instructions: [Instruction; PROGRAM],
+    data: [u32; TAPE],
+    ptr: usize,
+}
+
+impl<const PROGRAM: usize, const TAPE: usize, const STACK: usize> Machine<PROGRAM, TAPE, STACK> {
+    /// Fails to build for a `PROGRAM` or `TAPE` of 0, which would leave no
+    /// room for even the end of the program or a cell for the pointer.
+    pub fn new(config: Config) -> Self {
+        const { assert!(PROGRAM > 0 && TAPE > 0) }
+
+        Machine {
+            data: [config.fill(); TAPE],
+            config,
+            instructions: [Instruction::default(); PROGRAM],
+            ptr: 0,
+        }
+    }
+
+    pub fn compile(&mut self, source: &str) -> Result<(), CompileError> {
+        compile(
+            source,
+            &mut self.instructions,
+            &mut Stack::new([0; STACK]),
+            &self.config,
+        )
+    }
+
+    /// The tape as it was left by the last call to [`Machine::execute`].
+    pub fn tape(&self) -> &[u32] {
+        &self.data
+    }
+
+    /// Where the data pointer was left by the last call to [`Machine::execute`].
+    pub fn pointer(&self) -> usize {
+        self.ptr
+    }
+
+    /// Executes the compiled program on a blank tape.
+    ///
+    /// Failing to read or write is an [`InterpreterError::FailedToRead`] or
+    /// [`InterpreterError::FailedToWrite`], and
+    /// `#` with [`Config::debug_ops`] does nothing since there is nowhere to print to.
+    pub fn execute(
+        &mut self,
+        input: &mut impl Input,
+        out: &mut impl Output,
+    ) -> Result<(), InterpreterError> {
+        self.data = [self.config.fill(); TAPE];
+        self.ptr = 0;
+
+        let result = self.interpret(input, out);
+        let flushed = out
+            .flush()
+            .map_err(|_| InterpreterError::FailedToWrite { pc: None });
+
+        result.and(flushed)
+    }
+
+    fn interpret(
+        &mut self,
+        input: &mut impl Input,
+        out: &mut impl Output,
+    ) -> Result<(), InterpreterError> {
+        let data = &mut self.data;
+        let ptr = &mut self.ptr;
+        let mask = self.config.cell_width.mask();
+        let max_steps = self.config.max_steps.unwrap_or(u64::MAX);
+        let mut steps: u64 = 0;
+        let mut pc: usize = 0;
+
+        while !self.instructions[pc].operator.stops() {
+            check_limits(steps, max_steps, &self.config)?;
+
+            steps += 1;
+
+            let operand = self.instructions[pc].operand;
+
+            match self.instructions[pc].operator {
+                Op::IncDp => {
+                    *ptr = move_by(*ptr, operand, true, TAPE, self.config.pointer)
+                        .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr: *ptr })?
+                }
+                Op::DecDp => {
+                    *ptr = move_by(*ptr, operand, false, TAPE, self.config.pointer)
+                        .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr: *ptr })?
+                }
+                Op::ScanRight => {
+                    let mut first = true;
+
+                    while data[*ptr] != 0 {
+                        if !first {
+                            check_limits(steps, max_steps, &self.config)?;
+                            steps += 1;
+                        }
+
+                        first = false;
+                        *ptr = move_by(*ptr, operand, true, TAPE, self.config.pointer)
+                            .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr: *ptr })?
+                    }
+                }
+                Op::ScanLeft => {
+                    let mut first = true;
+
+                    while data[*ptr] != 0 {
+                        if !first {
+                            check_limits(steps, max_steps, &self.config)?;
+                            steps += 1;
+                        }
+
+                        first = false;
+                        *ptr = move_by(*ptr, operand, false, TAPE, self.config.pointer)
+                            .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr: *ptr })?
+                    }
+                }
+                Op::IncVal => data[*ptr] = data[*ptr].wrapping_add(operand as u32) & mask,
+                Op::DecVal => data[*ptr] = data[*ptr].wrapping_sub(operand as u32) & mask,
+                Op::Out => {
+                    let (bytes, len) = self.config.output_bytes(data[*ptr]);
+
+                    for &byte in &bytes[..len] {
+                        out.write_byte(byte)
+                            .map_err(|_| InterpreterError::FailedToWrite { pc: Some(pc) })?;
+                    }
+                }
+                Op::In => {
+                    out.flush()
+                        .map_err(|_| InterpreterError::FailedToWrite { pc: Some(pc) })?;
+
+                    match self.config.read_input(|| input.read_byte().map_err(|_| ())) {
+                        Ok(Some(value)) => data[*ptr] = value,
+                        Ok(None) => match self.config.eof {
+                            EofMode::Zero => data[*ptr] = 0,
+                            EofMode::NegativeOne => data[*ptr] = mask,
+                            EofMode::Unchanged => (),
+                        },
+                        Err(()) => return Err(InterpreterError::FailedToRead { pc }),
+                    }
+                }
+                Op::Clear => data[*ptr] = 0,
+                Op::SetConst => data[*ptr] = operand as u32 & mask,
+                Op::Dump => (),
+                Op::JmpFwd => {
+                    if data[*ptr] == 0 {
+                        pc = self.instructions[pc].operand
+                    }
+                }
+                Op::JmpBck => {
+                    if data[*ptr] != 0 {
+                        pc = self.instructions[pc].operand
+                    }
+                }
+                Op::End | Op::Halt => unreachable!("the loop stops before End and Halt"),
+            }
+
+            pc = pc.wrapping_add(1);
+        }
+
+        Ok(())
+    }
+}
+
+/// Fails if a run that has taken `steps` steps has to stop before the next one.
+fn check_limits(steps: u64, max_steps: u64, config: &Config) -> Result<(), InterpreterError> {
+    if steps == max_steps {
+        return Err(InterpreterError::StepLimitExceeded);
+    }
+
+    if config.interrupted() {
+        return Err(InterpreterError::Interrupted { steps });
+    }
+
+    Ok(())
+}
+
+/// Moves `n` cells either way on a tape of `len` cells that never grows.
+fn move_by(ptr: usize, n: usize, right: bool, len: usize, policy: PointerPolicy) -> Option<usize> {
+    let target = match right {
+        true => ptr.checked_add(n).filter(|&target| target < len),
+        false => ptr.checked_sub(n),
+    };
+
+    match (target, policy) {
+        (Some(target), _) => Some(target),
+        (None, PointerPolicy::Error) => None,
+        (None, PointerPolicy::Clamp) if right => Some(len - 1),
+        (None, PointerPolicy::Clamp) => Some(0),
+        (None, PointerPolicy::Wrap) if right => Some((ptr + n) % len),
+        (None, PointerPolicy::Wrap) => Some((ptr + len - n % len) % len),
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{CellWidth, Endian, EofMode, Op, OutputEncoding, Program};
+use std::io::{self, Write};
+
+impl Program {
+    /// Writes the compiled program out as a standalone C source file.
+    ///
+    /// The generated code does no bounds checking, so it only matches the
+    /// interpreter for programs that keep the pointer on the tape.
+    pub fn emit_c(&self, out: &mut impl Write) -> io::Result<()> {
+        let cell = match self.config.cell_width {
+            CellWidth::U8 => "unsigned char",
+            CellWidth::U16 => "uint16_t",
+            CellWidth::U32 => "uint32_t",
+        };
+        let bytes = match self.config.wide_input {
+            true => self.config.cell_width.bytes(),
+            false => 1,
+        };
+        let eof = match self.config.eof {
+            EofMode::Zero => "0",
+            EofMode::NegativeOne => "(cell)-1",
+            EofMode::Unchanged => "current",
+        };
+        let read = match self.config.input_endian {
+            Endian::Big => "value = (cell)((value << 8) | c);",
+            Endian::Little => "value |= (cell)((cell)c << (8 * i));",
+        };
+        // The cast that makes `printf` show the cell the way the interpreter would.
+        let number = match (self.config.signed, self.config.cell_width) {
+            (false, _) => "",
+            (true, CellWidth::U8) => "(int8_t)",
+            (true, CellWidth::U16) => "(int16_t)",
+            (true, CellWidth::U32) => "(int32_t)",
+        };
+        // How far to shift the cell for each byte `.` writes, putchar keeps the low byte.
+        let shifts = match self.config.wide_output {
+            true => (0..self.config.cell_width.bytes())
+                .map(|i| match self.config.output_endian {
+                    Endian::Big => (self.config.cell_width.bytes() - 1 - i) * 8,
+                    Endian::Little => i * 8,
+                })
+                .collect(),
+            false => vec![0],
+        };
+
+        writeln!(out, "#include <stdint.h>")?;
+        writeln!(out, "#include <stdio.h>")?;
+        writeln!(out, "#include <stdlib.h>")?;
+        writeln!(out)?;
+        writeln!(out, "typedef {} cell;", cell)?;
+        writeln!(out)?;
+
+        let program = self.instructions();
+
+        let reads = program
+            .iter()
+            .any(|instruction| instruction.operator == Op::In);
+        let writes = program
+            .iter()
+            .any(|instruction| instruction.operator == Op::Out);
+
+        if writes && !self.config.numeric_io && self.config.output_encoding == OutputEncoding::Utf8
+        {
+            writeln!(out, "static void put_utf8(uint32_t c) {{")?;
+            writeln!(
+                out,
+                "    if (c > 0x10FFFF || (c >= 0xD800 && c < 0xE000)) c = 0xFFFD;"
+            )?;
+            writeln!(out, "    if (c < 0x80) {{")?;
+            writeln!(out, "        putchar(c);")?;
+            writeln!(out, "    }} else if (c < 0x800) {{")?;
+            writeln!(out, "        putchar(0xC0 | c >> 6);")?;
+            writeln!(out, "        putchar(0x80 | (c & 0x3F));")?;
+            writeln!(out, "    }} else if (c < 0x10000) {{")?;
+            writeln!(out, "        putchar(0xE0 | c >> 12);")?;
+            writeln!(out, "        putchar(0x80 | (c >> 6 & 0x3F));")?;
+            writeln!(out, "        putchar(0x80 | (c & 0x3F));")?;
+            writeln!(out, "    }} else {{")?;
+            writeln!(out, "        putchar(0xF0 | c >> 18);")?;
+            writeln!(out, "        putchar(0x80 | (c >> 12 & 0x3F));")?;
+            writeln!(out, "        putchar(0x80 | (c >> 6 & 0x3F));")?;
+            writeln!(out, "        putchar(0x80 | (c & 0x3F));")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+
+        if reads && self.config.numeric_io {
+            writeln!(out, "static cell read_cell(cell current) {{")?;
+            writeln!(out, "    long long value;")?;
+            writeln!(out, "    int read = scanf(\"%lld\", &value);")?;
+            writeln!(out, "    (void)current;")?;
+            writeln!(out, "    if (read == EOF) return {};", eof)?;
+            writeln!(out, "    if (read != 1) exit(1);")?;
+            writeln!(out, "    return (cell)value;")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        } else if reads {
+            writeln!(out, "static cell read_cell(cell current) {{")?;
+            writeln!(out, "    cell value = 0;")?;
+            writeln!(out, "    (void)current;")?;
+            writeln!(out, "    for (int i = 0; i < {}; i++) {{", bytes)?;
+            writeln!(out, "        int c = getchar();")?;
+            writeln!(out, "        if (c == EOF) return {};", eof)?;
+            writeln!(out, "        {}", read)?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "    return value;")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+
+        writeln!(out, "int main(void) {{")?;
+        writeln!(
+            out,
+            "    cell *tape = calloc({}, sizeof(cell));",
+            self.config.max_tape
+        )?;
+
+        if self.config.fill() != 0 {
+            writeln!(
+                out,
+                "    for (size_t i = 0; i < {}; i++) tape[i] = {};",
+                self.config.max_tape,
+                self.config.fill()
+            )?;
+        }
+
+        writeln!(out, "    cell *ptr = tape;")?;
+
+        let mut depth = 1;
+
+        for instruction in program {
+            let n = instruction.operand;
+
+            if instruction.operator == Op::JmpBck {
+                depth -= 1;
+            }
+
+            let indent = "    ".repeat(depth);
+
+            match instruction.operator {
+                Op::End => (),
+                Op::IncDp => writeln!(out, "{}ptr += {};", indent, n)?,
+                Op::DecDp => writeln!(out, "{}ptr -= {};", indent, n)?,
+                Op::IncVal => writeln!(out, "{}*ptr += {};", indent, n)?,
+                Op::DecVal => writeln!(out, "{}*ptr -= {};", indent, n)?,
+                Op::Out if self.config.numeric_io => {
+                    writeln!(out, "{}printf(\"%lld \", (long long){}*ptr);", indent, number)?
+                }
+                Op::Out if self.config.output_encoding == OutputEncoding::Utf8 => {
+                    writeln!(out, "{}put_utf8(*ptr);", indent)?
+                }
+                Op::Out if self.config.output_encoding == OutputEncoding::Latin1 => {
+                    writeln!(out, "{}putchar(*ptr > 255 ? '?' : *ptr);", indent)?
+                }
+                Op::Out => {
+                    for shift in &shifts {
+                        match shift {
+                            0 => writeln!(out, "{}putchar(*ptr);", indent)?,
+                            _ => writeln!(out, "{}putchar(*ptr >> {});", indent, shift)?,
+                        }
+                    }
+                }
+                Op::In => writeln!(out, "{}*ptr = read_cell(*ptr);", indent)?,
+                Op::Clear => writeln!(out, "{}*ptr = 0;", indent)?,
+                Op::SetConst => writeln!(out, "{}*ptr = {};", indent, n as u64 & u64::from(self.config.cell_width.mask()))?,
+                Op::ScanRight => writeln!(out, "{}while (*ptr) ptr += {};", indent, n)?,
+                Op::ScanLeft => writeln!(out, "{}while (*ptr) ptr -= {};", indent, n)?,
+                Op::Halt => writeln!(out, "{}exit(0);", indent)?,
+                Op::Dump => writeln!(
+                    out,
+                    "{}fprintf(stderr, \"# ptr %ld = %lu\\n\", (long)(ptr - tape), (unsigned long)*ptr);",
+                    indent
+                )?,
+                Op::JmpFwd => {
+                    writeln!(out, "{}while (*ptr) {{", indent)?;
+                    depth += 1;
+                }
+                Op::JmpBck => writeln!(out, "{}}}", indent)?,
+            }
+        }
+
+        writeln!(out, "    free(tape);")?;
+        writeln!(out, "    return 0;")?;
+        writeln!(out, "}}")
+    }
+
+    /// Writes the compiled program out as a standalone Rust source file.
+    ///
+    /// Like [`Program::emit_c`] there is no pointer policy, but running off
+    /// either end of the tape panics instead of scribbling over memory.
+    pub fn emit_rust(&self, out: &mut impl Write) -> io::Result<()> {
+        let cell = match self.config.cell_width {
+            CellWidth::U8 => "u8",
+            CellWidth::U16 => "u16",
+            CellWidth::U32 => "u32",
+        };
+        let bytes = match self.config.wide_input {
+            true => self.config.cell_width.bytes(),
+            false => 1,
+        };
+        let eof = match self.config.eof {
+            EofMode::Zero => "0",
+            EofMode::NegativeOne => "Cell::MAX",
+            EofMode::Unchanged => "current",
+        };
+        let output = match (
+            self.config.output_encoding,
+            self.config.wide_output,
+            self.config.output_endian,
+        ) {
+            (OutputEncoding::Utf8, _, _) => {
+                "char::from_u32(tape[ptr] as u32).unwrap_or('\u{fffd}').encode_utf8(&mut [0; 4]).as_bytes()"
+            }
+            (OutputEncoding::Latin1, _, _) => "[if (tape[ptr] as u32) > 255 { b'?' } else { tape[ptr] as u8 }]",
+            (_, false, _) => "[tape[ptr] as u8]",
+            (_, true, Endian::Big) => "tape[ptr].to_be_bytes()",
+            (_, true, Endian::Little) => "tape[ptr].to_le_bytes()",
+        };
+        // The cast that makes `.` with numeric IO show the cell the way the interpreter would.
+        let number = match (self.config.signed, self.config.cell_width) {
+            (false, _) => "",
+            (true, CellWidth::U8) => " as i8",
+            (true, CellWidth::U16) => " as i16",
+            (true, CellWidth::U32) => " as i32",
+        };
+        let mask = u64::from(self.config.cell_width.mask());
+        let program = self.instructions();
+        let uses = |ops: &[Op]| {
+            program
+                .iter()
+                .any(|instruction| ops.contains(&instruction.operator))
+        };
+        // Only ask for `mut` where the program changes something, so the output builds without warnings.
+        let moves = match uses(&[Op::IncDp, Op::DecDp, Op::ScanRight, Op::ScanLeft]) {
+            true => "mut ",
+            false => "",
+        };
+        let writes = match uses(&[Op::IncVal, Op::DecVal, Op::In, Op::Clear, Op::SetConst]) {
+            true => "mut ",
+            false => "",
+        };
+        let reads = uses(&[Op::In]);
+
+        match reads {
+            true => writeln!(out, "use std::io::{{self, BufWriter, Read, Write}};")?,
+            false => writeln!(out, "use std::io::{{self, BufWriter, Write}};")?,
+        }
+
+        writeln!(out)?;
+        writeln!(out, "type Cell = {};", cell)?;
+        writeln!(out)?;
+
+        if reads && self.config.numeric_io {
+            let current = match self.config.eof {
+                EofMode::Unchanged => "current",
+                _ => "_current",
+            };
+
+            writeln!(
+                out,
+                "fn read_cell(input: &mut impl Read, {}: Cell) -> Cell {{",
+                current
+            )?;
+            writeln!(out, "    let mut text = String::new();")?;
+            writeln!(out, "    for byte in input.bytes() {{")?;
+            writeln!(out, "        let byte = byte.unwrap() as char;")?;
+            writeln!(out, "        if !byte.is_ascii_whitespace() {{")?;
+            writeln!(out, "            text.push(byte);")?;
+            writeln!(out, "        }} else if !text.is_empty() {{")?;
+            writeln!(out, "            break;")?;
+            writeln!(out, "        }}")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "    if text.is_empty() {{")?;
+            writeln!(out, "        return {};", eof)?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "    text.parse::<i64>().unwrap() as Cell")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        } else if reads {
+            let current = match self.config.eof {
+                EofMode::Unchanged => "current",
+                _ => "_current",
+            };
+            let value = match (bytes, self.config.input_endian) {
+                (1, _) => "Cell::from(buffer[0])",
+                (_, Endian::Big) => "buffer.iter().fold(0, |acc, &b| (acc << 8) | Cell::from(b))",
+                (_, Endian::Little) => "Cell::from_le_bytes(buffer)",
+            };
+
+            writeln!(
+                out,
+                "fn read_cell(input: &mut impl Read, {}: Cell) -> Cell {{",
+                current
+            )?;
+            writeln!(out, "    let mut buffer = [0u8; {}];", bytes)?;
+            writeln!(out, "    match input.read_exact(&mut buffer) {{")?;
+            writeln!(out, "        Ok(()) => {},", value)?;
+            writeln!(out, "        Err(_) => {},", eof)?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+
+        // Nothing after an `@` in the same block can run.
+        if uses(&[Op::Halt]) {
+            writeln!(out, "#[allow(unreachable_code)]")?;
+        }
+
+        writeln!(out, "fn main() {{")?;
+        writeln!(
+            out,
+            "    let {}tape: Vec<Cell> = vec![{}; {}];",
+            writes,
+            self.config.fill(),
+            self.config.max_tape
+        )?;
+        writeln!(out, "    let {}ptr: usize = 0;", moves)?;
+
+        if reads {
+            writeln!(out, "    let mut input = io::stdin().lock();")?;
+        }
+
+        writeln!(
+            out,
+            "    let mut out = BufWriter::new(io::stdout().lock());"
+        )?;
+
+        let mut depth = 1;
+
+        for instruction in program {
+            let n = instruction.operand;
+            // Cells wrap, so only the remainder of a long run makes a difference.
+            let value = n as u64 % (mask + 1);
+
+            if instruction.operator == Op::JmpBck {
+                depth -= 1;
+            }
+
+            let indent = "    ".repeat(depth);
+
+            match instruction.operator {
+                Op::End => (),
+                Op::IncDp => writeln!(out, "{}ptr += {};", indent, n)?,
+                Op::DecDp => writeln!(out, "{}ptr -= {};", indent, n)?,
+                Op::IncVal => writeln!(
+                    out,
+                    "{}tape[ptr] = tape[ptr].wrapping_add({});",
+                    indent, value
+                )?,
+                Op::DecVal => writeln!(
+                    out,
+                    "{}tape[ptr] = tape[ptr].wrapping_sub({});",
+                    indent, value
+                )?,
+                Op::Out => {
+                    match self.config.numeric_io {
+                        true => writeln!(
+                            out,
+                            "{}write!(out, \"{{}} \", tape[ptr]{}).unwrap();",
+                            indent, number
+                        )?,
+                        false => writeln!(out, "{}out.write_all(&{}).unwrap();", indent, output)?,
+                    }
+
+                    if self.config.unbuffered {
+                        writeln!(out, "{}out.flush().unwrap();", indent)?;
+                    }
+                }
+                Op::In => {
+                    writeln!(out, "{}out.flush().unwrap();", indent)?;
+                    writeln!(
+                        out,
+                        "{}tape[ptr] = read_cell(&mut input, tape[ptr]);",
+                        indent
+                    )?;
+                }
+                Op::Clear => writeln!(out, "{}tape[ptr] = 0;", indent)?,
+                Op::SetConst => writeln!(out, "{}tape[ptr] = {};", indent, value)?,
+                Op::ScanRight => {
+                    writeln!(out, "{}while tape[ptr] != 0 {{ ptr += {}; }}", indent, n)?
+                }
+                Op::ScanLeft => {
+                    writeln!(out, "{}while tape[ptr] != 0 {{ ptr -= {}; }}", indent, n)?
+                }
+                Op::Halt => {
+                    writeln!(out, "{}out.flush().unwrap();", indent)?;
+                    writeln!(out, "{}std::process::exit(0);", indent)?;
+                }
+                Op::Dump => {
+                    writeln!(out, "{}out.flush().unwrap();", indent)?;
+                    writeln!(
+                        out,
+                        "{}eprintln!(\"# ptr {{}} = {{}}\", ptr, tape[ptr]);",
+                        indent
+                    )?;
+                }
+                Op::JmpFwd => {
+                    writeln!(out, "{}while tape[ptr] != 0 {{", indent)?;
+                    depth += 1;
+                }
+                Op::JmpBck => writeln!(out, "{}}}", indent)?,
+            }
+        }
+
+        writeln!(out, "    out.flush().unwrap();")?;
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+/// Where a [`Program`](crate::Program) given one with
+/// [`Program::with_input`](crate::Program::with_input) gets the bytes for `,`.
+///
+/// A byte is asked for only when a `,` runs, so an implementation can hand
+/// them out lazily, prompt for them or make them up in a test. `None` is the
+/// end of the input and `,` does whatever [`Config::eof`](crate::Config::eof)
+/// says.
+pub trait BfInput {
+    fn read_byte(&mut self) -> Option<u8>;
+}
+
+/// Reads stdin a byte at a time. A read that fails counts as the end of the
+/// input.
+impl BfInput for io::Stdin {
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut byte = [0];
+
+        self.read_exact(&mut byte).ok().map(|_| byte[0])
+    }
+}
+
+/// Hands out the bytes from the front, taking each one out as it goes.
+impl BfInput for Vec<u8> {
+    fn read_byte(&mut self) -> Option<u8> {
+        match self.is_empty() {
+            true => None,
+            false => Some(self.remove(0)),
+        }
+    }
+}
+
+/// Pops bytes off the front. Unlike a `Vec` that doesn't move the rest along
+/// every time, so it's the one for long input.
+impl BfInput for VecDeque<u8> {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.pop_front()
+    }
+}
+
+impl<T: BfInput + ?Sized> BfInput for Box<T> {
+    fn read_byte(&mut self) -> Option<u8> {
+        (**self).read_byte()
+    }
+}
+
+/// Shares the input with whoever else holds on to it, so they can keep adding
+/// bytes between runs, like a `Rc<RefCell<VecDeque<u8>>>` pushed on from
+/// outside between calls to [`Program::execute`](crate::Program::execute).
+impl<T: BfInput + ?Sized> BfInput for Rc<RefCell<T>> {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.borrow_mut().read_byte()
+    }
+}
+
+/// Lets the interpreter, which reads through [`Read`], take its bytes from a
+/// [`BfInput`].
+pub(crate) struct Reader<'a>(pub(crate) &'a mut dyn BfInput);
+
+impl Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match buf.first_mut() {
+            Some(first) => Ok(self.0.read_byte().map_or(0, |byte| {
+                *first = byte;
+                1
+            })),
+            None => Ok(0),
+        }
+    }
+}
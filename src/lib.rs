@@ -0,0 +1,1596 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod breakpoint;
+#[cfg(feature = "std")]
+mod compiled;
+#[cfg(feature = "std")]
+mod dialect;
+#[cfg(feature = "std")]
+mod dispatch;
+pub mod embedded;
+#[cfg(feature = "std")]
+mod emit;
+#[cfg(feature = "std")]
+mod input;
+#[cfg(feature = "std")]
+mod lint;
+#[cfg(feature = "std")]
+mod pragma;
+#[cfg(feature = "std")]
+mod tape;
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use embedded::Stack;
+use embedded::StackErrors;
+#[cfg(feature = "std")]
+use std::io::{self, BufWriter, Read, Write};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+pub use breakpoint::{Comparison, Condition};
+pub use embedded::{tokens, Instruction, Op};
+#[cfg(feature = "std")]
+pub use input::BfInput;
+#[cfg(feature = "std")]
+pub use lint::Lint;
+#[cfg(feature = "std")]
+pub use tape::Tape;
+
+/// Width of a single tape cell, which decides where arithmetic wraps.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum CellWidth {
+    #[default]
+    U8,
+    U16,
+    U32,
+}
+
+impl CellWidth {
+    pub fn from_bits(bits: u32) -> Option<CellWidth> {
+        match bits {
+            8 => Some(CellWidth::U8),
+            16 => Some(CellWidth::U16),
+            32 => Some(CellWidth::U32),
+            _ => None,
+        }
+    }
+
+    pub fn bytes(&self) -> usize {
+        match self {
+            CellWidth::U8 => 1,
+            CellWidth::U16 => 2,
+            CellWidth::U32 => 4,
+        }
+    }
+
+    pub fn mask(&self) -> u32 {
+        match self {
+            CellWidth::U8 => u8::MAX.into(),
+            CellWidth::U16 => u16::MAX.into(),
+            CellWidth::U32 => u32::MAX,
+        }
+    }
+}
+
+/// What `,` stores in the current cell once input runs out.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum EofMode {
+    #[default]
+    Zero,
+    NegativeOne,
+    Unchanged,
+}
+
+impl EofMode {
+    pub fn from_name(name: &str) -> Option<EofMode> {
+        match name {
+            "zero" => Some(EofMode::Zero),
+            "minusone" => Some(EofMode::NegativeOne),
+            "unchanged" => Some(EofMode::Unchanged),
+            _ => None,
+        }
+    }
+}
+
+/// Which byte of a wide cell comes first.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+impl Endian {
+    pub fn from_name(name: &str) -> Option<Endian> {
+        match name {
+            "big" | "be" => Some(Endian::Big),
+            "little" | "le" => Some(Endian::Little),
+            _ => None,
+        }
+    }
+}
+
+/// How `.` turns a cell into bytes.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum OutputEncoding {
+    /// The low byte, or every byte with [`Config::wide_output`].
+    #[default]
+    Bytes,
+    /// The cell is a code point, written as UTF-8. Anything that isn't one,
+    /// like a surrogate, comes out as U+FFFD.
+    Utf8,
+    /// The cell is a Latin-1 character, so anything over 255 comes out as `?`.
+    Latin1,
+}
+
+impl OutputEncoding {
+    pub fn from_name(name: &str) -> Option<OutputEncoding> {
+        match name {
+            "bytes" => Some(OutputEncoding::Bytes),
+            "utf8" | "utf-8" => Some(OutputEncoding::Utf8),
+            "latin1" | "latin-1" => Some(OutputEncoding::Latin1),
+            _ => None,
+        }
+    }
+}
+
+/// What happens when the data pointer is moved off either edge of the tape.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum PointerPolicy {
+    #[default]
+    Error,
+    Wrap,
+    Clamp,
+}
+
+impl PointerPolicy {
+    pub fn from_name(name: &str) -> Option<PointerPolicy> {
+        match name {
+            "error" => Some(PointerPolicy::Error),
+            "wrap" => Some(PointerPolicy::Wrap),
+            "clamp" => Some(PointerPolicy::Clamp),
+            _ => None,
+        }
+    }
+}
+
+/// How the interpreter picks the code to run for each instruction.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum Dispatch {
+    /// A table of handler functions indexed by the operator, which benchmarks
+    /// kept coming out quicker. Tracing, the debugger and profiling still go
+    /// through the `match`.
+    #[default]
+    Table,
+    /// One big `match` on the operator, the way it always used to be.
+    Match,
+}
+
+impl Dispatch {
+    pub fn from_name(name: &str) -> Option<Dispatch> {
+        match name {
+            "match" => Some(Dispatch::Match),
+            "table" => Some(Dispatch::Table),
+            _ => None,
+        }
+    }
+}
+
+/// The language a program is written in, everything but brainf**k itself
+/// goes through [`Dialect::translate`] before compiling.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub enum Dialect {
+    #[default]
+    Brainfuck,
+    /// `Ook.`, `Ook?` and `Ook!` in pairs, `Ook. Ook?` being `>` and so on.
+    Ook,
+    /// Ook! with the `Ook`s left out, so `.?` is `>`.
+    ShortOok,
+}
+
+impl Dialect {
+    pub fn from_name(name: &str) -> Option<Dialect> {
+        match name {
+            "bf" => Some(Dialect::Brainfuck),
+            "ook" => Some(Dialect::Ook),
+            "short-ook" => Some(Dialect::ShortOok),
+            _ => None,
+        }
+    }
+}
+
+/// Execution counts gathered by a profiled run.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    /// How often each operator ran, indexed by `Op as usize`.
+    pub op_counts: [u64; Op::ALL.len()],
+    /// How often the loop opened at each instruction index was entered.
+    pub loop_entries: Vec<u64>,
+    pub elapsed: Duration,
+}
+
+#[cfg(feature = "std")]
+impl Profile {
+    pub fn count(&self, op: Op) -> u64 {
+        self.op_counts[op as usize]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.op_counts.iter().sum()
+    }
+}
+
+/// What a successful run did.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunStats {
+    /// Instructions executed, after any optimization.
+    pub steps: u64,
+    pub output_bytes: u64,
+    pub final_ptr: usize,
+    /// The furthest right the pointer got.
+    pub max_ptr: usize,
+    /// The biggest value any cell held, as raw bits like [`Program::tape`].
+    pub max_value: u32,
+    /// The run stopped at an `@` with [`Config::debug_ops`] rather than the
+    /// end of the program.
+    pub halted: bool,
+}
+
+/// The optimizations [`Config::optimize`] can turn on, one by one.
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct Passes {
+    /// Collapse runs of `+`, `-`, `<` and `>` into single instructions.
+    pub coalesce: bool,
+    /// Replace clear loops like `[-]` with a single [`Op::Clear`].
+    pub clear: bool,
+    /// Replace scan loops like `[>]` with a search for the next zero cell.
+    pub scan: bool,
+    /// Fuse a clear and the `+` and `-` right after it, like `[-]+++++`, into
+    /// a single [`Op::SetConst`]. Needs `clear` to have any clears to fuse.
+    pub set: bool,
+}
+
+impl Passes {
+    pub const NONE: Passes = Passes {
+        coalesce: false,
+        clear: false,
+        scan: false,
+        set: false,
+    };
+    /// What `--optimize` turns on.
+    pub const ALL: Passes = Passes {
+        coalesce: true,
+        clear: true,
+        scan: true,
+        set: true,
+    };
+}
+
+/// Execution options for a [`Program`].
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub cell_width: CellWidth,
+    /// Read a whole cell per `,`, in [`Config::input_endian`] order, instead
+    /// of a single byte.
+    pub wide_input: bool,
+    pub input_endian: Endian,
+    /// Write every byte of the cell per `.`, in [`Config::output_endian`]
+    /// order, instead of only its low byte. Makes no difference to 8 bit cells,
+    /// or to an [`OutputEncoding`] other than `Bytes`.
+    pub wide_output: bool,
+    pub output_endian: Endian,
+    /// `,` reads a decimal number, skipping any whitespace in front of it,
+    /// and `.` writes the cell as one followed by a space, instead of
+    /// bytes. Numbers wrap to fit the cell the same way `+` and `-` do.
+    pub numeric_io: bool,
+    /// Only used by `.` without [`Config::numeric_io`].
+    pub output_encoding: OutputEncoding,
+    pub eof: EofMode,
+    /// What every cell holds before anything writes to it, cells the tape
+    /// grows by included. Masked to the cell width, and loops still only
+    /// stop on 0.
+    pub init_cell: u32,
+    /// Number of cells the tape starts out with.
+    pub tape_size: usize,
+    /// Upper bound the tape may grow to before execution fails.
+    pub max_tape: usize,
+    /// Only allocate the parts of the tape that get written to, see [`Tape`].
+    ///
+    /// Slower to run on, but a huge `tape_size` no longer costs a huge
+    /// allocation. [`embedded::Machine`] always has a fixed array.
+    pub sparse: bool,
+    pub pointer: PointerPolicy,
+    /// Most instructions a program may compile to, no limit by default. A
+    /// [`Program`] only ever makes room for as many as its source could hold.
+    pub program_size: usize,
+    /// How deeply `[` may nest in a program. The bracket stack only grows as
+    /// deep as the program actually nests, so a big limit costs nothing.
+    pub stack_size: usize,
+    /// Which optimizations to compile with, none by default.
+    pub optimize: Passes,
+    /// Flush stdout after every `.` instead of buffering output.
+    pub unbuffered: bool,
+    pub dispatch: Dispatch,
+    /// Step through the program one instruction at a time, driven from stdin.
+    pub debug: bool,
+    /// Count executed instructions and loop entries, see [`Program::profile`].
+    pub profile: bool,
+    /// Abort with [`InterpreterError::StepLimitExceeded`] after this many instructions.
+    /// A scan counts each move past its first as another one.
+    pub max_steps: Option<u64>,
+    /// Abort with [`InterpreterError::Timeout`] once the program has been
+    /// running for this long.
+    ///
+    /// The clock is only looked at every [`TIMEOUT_CHECK_STEPS`] steps, so a
+    /// run can go a little over.
+    pub timeout: Option<Duration>,
+    /// Abort with [`InterpreterError::Interrupted`] as soon as this is set,
+    /// say by a Ctrl-C handler. Nothing clears it again afterwards.
+    pub interrupt: Option<&'static AtomicBool>,
+    /// Abort with [`InterpreterError::OutputLimitExceeded`] rather than let `.`
+    /// write more than this many bytes in one run.
+    pub max_output: Option<u64>,
+    /// Abort with [`InterpreterError::LoopDepthExceeded`] when loops nest deeper than this.
+    /// Loops that optimizing folds into a single instruction still count.
+    pub max_loop_depth: Option<usize>,
+    /// Compile `#` into [`Op::Dump`], which prints the cells around the pointer,
+    /// and `@` into [`Op::Halt`], which stops the run there.
+    pub debug_ops: bool,
+    /// Log every executed instruction to stderr.
+    pub trace: bool,
+    /// Print the cells around the pointer to stderr after every this many
+    /// executed instructions, like a `#` that goes off on its own.
+    pub dump_every: Option<u64>,
+    /// Stop before running the instruction at this index.
+    pub break_at: Option<usize>,
+    /// Stop once this becomes true. It has to turn false again before it
+    /// stops a second time, rather than stopping at every step it holds for.
+    pub break_when: Option<Condition>,
+    /// What stopping at [`Config::break_at`] or [`Config::break_when`] does:
+    /// drop into the debugger, like [`Config::debug`], or end the run with
+    /// [`InterpreterError::Breakpoint`].
+    pub break_exit: bool,
+    /// Treat cells as two's complement signed numbers of [`Config::cell_width`] bits.
+    ///
+    /// The bits in a cell, and so loops and `.`, are exactly the same as for
+    /// unsigned cells: `.` still writes the low byte, so -1 comes out as 0xff.
+    /// Only how the interpreter shows a value changes, so `-` on a zero cell
+    /// reads as -1 in dumps and traces rather than 255.
+    pub signed: bool,
+    /// Reject anything in the source that isn't an instruction or whitespace
+    /// instead of skipping it as a comment.
+    pub strict: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cell_width: CellWidth::default(),
+            wide_input: false,
+            input_endian: Endian::default(),
+            wide_output: false,
+            output_endian: Endian::default(),
+            numeric_io: false,
+            output_encoding: OutputEncoding::default(),
+            eof: EofMode::default(),
+            init_cell: 0,
+            tape_size: TAPE_SIZE,
+            max_tape: MAX_TAPE,
+            sparse: false,
+            pointer: PointerPolicy::default(),
+            program_size: usize::MAX,
+            stack_size: STACK_SIZE,
+            optimize: Passes::NONE,
+            unbuffered: false,
+            dispatch: Dispatch::default(),
+            debug: false,
+            profile: false,
+            max_steps: None,
+            timeout: None,
+            interrupt: None,
+            max_output: None,
+            max_loop_depth: None,
+            debug_ops: false,
+            trace: false,
+            dump_every: None,
+            break_at: None,
+            break_when: None,
+            break_exit: false,
+            signed: false,
+            strict: false,
+        }
+    }
+}
+
+impl Config {
+    /// Whether writing `len` more bytes after `written` would go past
+    /// [`Config::max_output`].
+    #[cfg(feature = "std")]
+    pub(crate) fn over_output_limit(&self, written: u64, len: usize) -> bool {
+        self.max_output
+            .is_some_and(|max_output| written + len as u64 > max_output)
+    }
+
+    /// Whether [`Config::interrupt`] has been set.
+    pub(crate) fn interrupted(&self) -> bool {
+        self.interrupt
+            .is_some_and(|interrupt| interrupt.load(Ordering::Relaxed))
+    }
+
+    /// [`Config::init_cell`] as a cell, what every cell holds until it is written.
+    pub fn fill(&self) -> u32 {
+        self.init_cell & self.cell_width.mask()
+    }
+
+    /// The number the raw bits of a cell stand for under this config.
+    pub fn cell_value(&self, raw: u32) -> i64 {
+        let bits = self.cell_width.bytes() * 8;
+
+        match self.signed && raw >> (bits - 1) & 1 == 1 {
+            true => i64::from(raw) - (1i64 << bits),
+            false => i64::from(raw),
+        }
+    }
+
+    /// The cell `,` reads from the bytes of a wide cell.
+    fn input_value(&self, bytes: &[u8]) -> u32 {
+        let shift = |acc: u32, &b: &u8| (acc << 8) | u32::from(b);
+
+        match self.input_endian {
+            Endian::Big => bytes.iter().fold(0, shift),
+            Endian::Little => bytes.iter().rev().fold(0, shift),
+        }
+    }
+
+    /// What `,` reads, taking bytes from `next` until it has the whole cell.
+    /// `None` is the end of the input, even part way through a wide cell.
+    fn read_input(
+        &self,
+        mut next: impl FnMut() -> Result<Option<u8>, ()>,
+    ) -> Result<Option<u32>, ()> {
+        if self.numeric_io {
+            return self.read_number(next);
+        }
+
+        let len = match self.wide_input {
+            true => self.cell_width.bytes(),
+            false => 1,
+        };
+        let mut buffer = [0u8; 4];
+
+        for byte in buffer[..len].iter_mut() {
+            match next()? {
+                Some(read) => *byte = read,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(self.input_value(&buffer[..len])))
+    }
+
+    /// Reads the number `,` takes with [`Config::numeric_io`] a byte at a time
+    /// from `next`, `None` being the end of the input before any digits.
+    ///
+    /// Anything but whitespace or the end right after the digits, or a number
+    /// without any, fails.
+    fn read_number(
+        &self,
+        mut next: impl FnMut() -> Result<Option<u8>, ()>,
+    ) -> Result<Option<u32>, ()> {
+        let mut byte = next()?;
+
+        while byte.is_some_and(|b| b.is_ascii_whitespace()) {
+            byte = next()?;
+        }
+
+        let negative = byte == Some(b'-');
+        let mut value: u32 = 0;
+        let mut digits = 0;
+
+        if negative {
+            byte = next()?;
+        }
+
+        while let Some(digit) = byte.filter(u8::is_ascii_digit) {
+            value = value.wrapping_mul(10).wrapping_add(u32::from(digit - b'0'));
+            digits += 1;
+            byte = next()?;
+        }
+
+        if negative {
+            value = value.wrapping_neg();
+        }
+
+        match (digits, byte) {
+            (0, None) if !negative => Ok(None),
+            (0, _) => Err(()),
+            (_, Some(b)) if !b.is_ascii_whitespace() => Err(()),
+            _ => Ok(Some(value & self.cell_width.mask())),
+        }
+    }
+
+    /// The bytes `.` writes for a cell holding `raw`, the first `len` of the array.
+    fn output_bytes(&self, raw: u32) -> ([u8; 12], usize) {
+        let mut bytes = [0; 12];
+
+        // At most 11 characters for -2147483648 and the space.
+        if self.numeric_io {
+            let value = self.cell_value(raw);
+            let mut digits = value.unsigned_abs();
+            let mut len = 0;
+
+            loop {
+                bytes[len] = b'0' + (digits % 10) as u8;
+                digits /= 10;
+                len += 1;
+
+                if digits == 0 {
+                    break;
+                }
+            }
+
+            if value < 0 {
+                bytes[len] = b'-';
+                len += 1;
+            }
+
+            bytes[..len].reverse();
+            bytes[len] = b' ';
+
+            return (bytes, len + 1);
+        }
+
+        match self.output_encoding {
+            OutputEncoding::Bytes => (),
+            OutputEncoding::Utf8 => {
+                let c = char::from_u32(raw).unwrap_or(char::REPLACEMENT_CHARACTER);
+
+                let len = c.encode_utf8(&mut bytes).len();
+
+                return (bytes, len);
+            }
+            OutputEncoding::Latin1 => {
+                bytes[0] = u8::try_from(raw).unwrap_or(b'?');
+
+                return (bytes, 1);
+            }
+        }
+
+        let len = match self.wide_output {
+            true => self.cell_width.bytes(),
+            false => 1,
+        };
+
+        for (i, byte) in bytes[..len].iter_mut().enumerate() {
+            let shift = match self.output_endian {
+                Endian::Big => len - 1 - i,
+                Endian::Little => i,
+            };
+
+            *byte = (raw >> (shift * 8)) as u8;
+        }
+
+        (bytes, len)
+    }
+}
+
+const STACK_SIZE: usize = 512;
+const TAPE_SIZE: usize = 30000;
+const MAX_TAPE: usize = 1 << 24;
+/// How many steps run between looks at the clock for [`Config::timeout`].
+pub const TIMEOUT_CHECK_STEPS: u64 = 1 << 16;
+
+/// What `,` gets from `input`: the new cell, `None` at the end of the input,
+/// or an error when reading fails.
+#[cfg(feature = "std")]
+fn read_input(input: &mut dyn Read, config: &Config) -> Result<Option<u32>, ()> {
+    config.read_input(|| {
+        let mut byte = [0];
+
+        match input.read_exact(&mut byte) {
+            Ok(()) => Ok(Some(byte[0])),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(_) => Err(()),
+        }
+    })
+}
+
+/// Fails if a run that has taken `steps` steps has to stop before the next one.
+#[cfg(feature = "std")]
+fn check_limits(
+    steps: u64,
+    max_steps: u64,
+    deadline: Option<Instant>,
+    config: &Config,
+) -> Result<(), InterpreterError> {
+    if steps == max_steps {
+        return Err(InterpreterError::StepLimitExceeded);
+    }
+
+    // Looking at the clock every step would cost more than most instructions do.
+    if steps.is_multiple_of(TIMEOUT_CHECK_STEPS)
+        && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        return Err(InterpreterError::Timeout { steps });
+    }
+
+    if config.interrupted() {
+        return Err(InterpreterError::Interrupted { steps });
+    }
+
+    Ok(())
+}
+
+/// The cell under the pointer, for the instruction at `pc`.
+///
+/// Moving the pointer already keeps it on the tape however [`Config::pointer`]
+/// says, so this only fails, rather than panicking, if a pointer got past that.
+#[cfg(feature = "std")]
+fn read_cell(data: &Tape, ptr: usize, pc: usize) -> Result<u32, InterpreterError> {
+    data.get(ptr)
+        .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr })
+}
+
+/// Sets the cell under the pointer, failing the same way as [`read_cell`].
+#[cfg(feature = "std")]
+fn write_cell(data: &mut Tape, ptr: usize, pc: usize, value: u32) -> Result<(), InterpreterError> {
+    let cell = data
+        .get_mut(ptr)
+        .ok_or(InterpreterError::PointerOutOfBounds { pc, ptr })?;
+
+    *cell = value;
+
+    Ok(())
+}
+
+/// Moves the pointer `n` cells right, growing the tape as it goes.
+#[cfg(feature = "std")]
+fn move_right(data: &mut Tape, ptr: usize, n: usize, config: &Config) -> Option<usize> {
+    let target = ptr + n;
+
+    if target >= config.max_tape {
+        data.resize(config.max_tape, config.fill());
+
+        return match config.pointer {
+            PointerPolicy::Error => None,
+            PointerPolicy::Wrap => Some(target % config.max_tape),
+            PointerPolicy::Clamp => Some(config.max_tape - 1),
+        };
+    }
+
+    if target >= data.len() {
+        data.resize(
+            (data.len() * 2).max(target + 1).min(config.max_tape),
+            config.fill(),
+        );
+    }
+
+    Some(target)
+}
+
+/// Moves the pointer `n` cells left, the tape only grows if it wraps round
+/// to the far end of [`Config::max_tape`].
+#[cfg(feature = "std")]
+fn move_left(data: &mut Tape, ptr: usize, n: usize, config: &Config) -> Option<usize> {
+    match ptr.checked_sub(n) {
+        Some(target) => Some(target),
+        None => match config.pointer {
+            PointerPolicy::Error => None,
+            PointerPolicy::Wrap => {
+                data.resize(config.max_tape, config.fill());
+
+                Some((ptr + config.max_tape - n % config.max_tape) % config.max_tape)
+            }
+            PointerPolicy::Clamp => Some(0),
+        },
+    }
+}
+
+/// The byte order mark some editors (mostly on Windows) put at the start of a file.
+const BOM: char = '\u{feff}';
+
+/// `source` without the byte order mark in front of it, if it has one.
+fn strip_bom(source: &str) -> &str {
+    source.strip_prefix(BOM).unwrap_or(source)
+}
+
+/// Pairs every character of `source` with its 1-based line and column.
+fn source_positions(source: &str) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+    source.chars().scan((1, 0), |(line, column), c| {
+        match c {
+            '\n' => {
+                *line += 1;
+                *column = 0;
+            }
+            _ => *column += 1,
+        }
+
+        Some((*line, *column, c))
+    })
+}
+
+/// Formats the cells within `radius` of `ptr`, with the current one in brackets.
+#[cfg(feature = "std")]
+fn tape_window(data: &Tape, ptr: usize, radius: usize, config: &Config) -> String {
+    let start = ptr.saturating_sub(radius);
+    let end = (ptr + radius + 1).min(data.len());
+
+    (start..end)
+        .map(|i| match i == ptr {
+            true => format!("{}:[{}]", i, config.cell_value(data[i])),
+            false => format!("{}:{}", i, config.cell_value(data[i])),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(feature = "std")]
+enum DebugCommand {
+    Step,
+    Continue,
+    Quit,
+}
+
+/// Shows the instruction about to run and waits for a debugger command on stdin.
+#[cfg(feature = "std")]
+fn debug_prompt(
+    pc: usize,
+    instruction: &Instruction,
+    data: &Tape,
+    ptr: usize,
+    config: &Config,
+) -> DebugCommand {
+    eprintln!(
+        "pc {:04} {:?} {} | ptr {} = {}",
+        pc,
+        instruction.operator,
+        instruction.operand,
+        ptr,
+        config.cell_value(data[ptr])
+    );
+
+    loop {
+        eprint!("(s)tep (c)ontinue (p)rint (q)uit> ");
+
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => return DebugCommand::Continue,
+            Ok(_) => (),
+        }
+
+        match line.trim() {
+            "" | "s" => return DebugCommand::Step,
+            "c" => return DebugCommand::Continue,
+            "q" => return DebugCommand::Quit,
+            "p" => eprintln!("{}", tape_window(data, ptr, 8, config)),
+            other => eprintln!("unknown command '{}'", other),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompileError {
+    /// The source has more than [`Config::program_size`] instructions.
+    ProgramTooLarge { limit: usize },
+    /// A `[` nests deeper than [`Config::stack_size`] allows.
+    StackOverflow {
+        line: usize,
+        column: usize,
+        limit: usize,
+    },
+    /// A `]` has no `[` to close.
+    UnmatchedBracket { line: usize, column: usize },
+    /// A `[` is never closed.
+    UnclosedBracket { line: usize, column: usize },
+    /// A character that is neither an instruction nor whitespace, only an
+    /// error with [`Config::strict`].
+    UnexpectedChar {
+        ch: char,
+        line: usize,
+        column: usize,
+    },
+    /// Something in an Ook! program that isn't a token, or a pair of tokens
+    /// that doesn't stand for an instruction.
+    MalformedOok { line: usize, column: usize },
+}
+
+impl CompileError {
+    /// Maps a bracket stack failure at `line` and `column` to the error it means
+    /// for the program, an underflow being a `]` with nothing left to close.
+    fn from_stack(e: StackErrors, line: usize, column: usize, limit: usize) -> CompileError {
+        match e {
+            StackErrors::OverFlow => CompileError::StackOverflow {
+                line,
+                column,
+                limit,
+            },
+            StackErrors::UnderFlow => CompileError::UnmatchedBracket { line, column },
+        }
+    }
+
+    /// The line and column in the source the error points at, if any.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match *self {
+            CompileError::ProgramTooLarge { .. } => None,
+            CompileError::StackOverflow { line, column, .. }
+            | CompileError::UnmatchedBracket { line, column }
+            | CompileError::UnclosedBracket { line, column }
+            | CompileError::UnexpectedChar { line, column, .. }
+            | CompileError::MalformedOok { line, column } => Some((line, column)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CompileError {
+    /// Other brackets in `source` worth pointing at along with a bracket
+    /// error, as lines and columns.
+    ///
+    /// For an unclosed `[` that is every other `[` still open at the end,
+    /// outermost first. For an unmatched `]` it is the `[` of the last loop
+    /// that closed before it, which is often the one that was closed too
+    /// early. Any other error has none.
+    pub fn related_brackets(&self, source: &str) -> Vec<(usize, usize)> {
+        let (target, unmatched) = match *self {
+            CompileError::UnmatchedBracket { line, column } => ((line, column), true),
+            CompileError::UnclosedBracket { line, column } => ((line, column), false),
+            _ => return Vec::new(),
+        };
+        let mut open = Vec::new();
+        let mut last_closed = None;
+
+        for (line, column, c) in source_positions(strip_bom(source)) {
+            match c {
+                ']' if unmatched && (line, column) == target => {
+                    return last_closed.into_iter().collect();
+                }
+                '[' => open.push((line, column)),
+                ']' => last_closed = open.pop().or(last_closed),
+                _ => (),
+            }
+        }
+
+        open.retain(|&position| position != target);
+        open
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::ProgramTooLarge { limit } => {
+                write!(f, "program is longer than {} instructions", limit)
+            }
+            CompileError::StackOverflow { limit, .. } => {
+                write!(f, "loops nest deeper than {} levels", limit)
+            }
+            CompileError::UnmatchedBracket { .. } => write!(f, "unmatched ']'"),
+            CompileError::UnclosedBracket { .. } => write!(f, "unclosed '['"),
+            CompileError::UnexpectedChar { ch, .. } => {
+                write!(f, "unexpected character {:?}", ch)
+            }
+            CompileError::MalformedOok { .. } => write!(f, "not a valid Ook! instruction"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// Reading the input for the `,` at `pc` failed.
+    FailedToRead {
+        pc: usize,
+    },
+    /// Writing the output failed, either for the instruction at `pc` or, without
+    /// one, while flushing it once the run was over.
+    FailedToWrite {
+        pc: Option<usize>,
+    },
+    FailedToCompile(CompileError),
+    /// The instruction at `pc` tried to move the pointer off the tape from `ptr`.
+    PointerOutOfBounds {
+        pc: usize,
+        ptr: usize,
+    },
+    /// The program ran for longer than [`Config::max_steps`] allowed.
+    StepLimitExceeded,
+    /// The program was still going after [`Config::timeout`], having run `steps` instructions.
+    Timeout {
+        steps: u64,
+    },
+    /// The `.` at `pc` would have taken the output past [`Config::max_output`],
+    /// with `bytes` written before it.
+    OutputLimitExceeded {
+        pc: usize,
+        bytes: u64,
+    },
+    /// [`Config::interrupt`] got set after `steps` instructions.
+    Interrupted {
+        steps: u64,
+    },
+    /// Entering the loop at `pc` nested deeper than [`Config::max_loop_depth`] allowed.
+    LoopDepthExceeded {
+        pc: usize,
+    },
+    /// A breakpoint went off before the instruction at `pc` with
+    /// [`Config::break_exit`] set. Resuming stops there again unless the
+    /// breakpoint is taken out.
+    Breakpoint {
+        pc: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+pub struct Program {
+    config: Config,
+    /// The compiled program and the End that terminates it, plus whatever room
+    /// compiling made that the program didn't need.
+    instructions: Vec<Instruction>,
+    stack: Stack<Vec<usize>>,
+    data: Tape,
+    ptr: usize,
+    /// The next instruction to run, kept between runs so they can be resumed.
+    pc: usize,
+    /// How many loops `pc` is inside of, kept with it so resuming or stepping
+    /// doesn't have to work it out again.
+    depth: usize,
+    profile: Option<Profile>,
+    /// What [`Program::execute`] reads from instead of stdin, if anything.
+    input: Option<Box<dyn BfInput>>,
+}
+
+/// What one [`Program::step`] did.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StepResult {
+    /// The program is at its end, so stepping again does nothing.
+    pub ended: bool,
+    /// The instruction the next step runs.
+    pub pc: usize,
+    /// What the step wrote, which is nothing unless it was a `.`, and with
+    /// [`Config::wide_output`] can be more than one byte.
+    pub output: Vec<u8>,
+}
+
+/// Everything a [`Program`] needs to carry on running from where it was, see
+/// [`Program::snapshot`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct State {
+    data: Tape,
+    ptr: usize,
+    pc: usize,
+    depth: usize,
+}
+
+#[cfg(feature = "std")]
+impl State {
+    pub fn tape(&self) -> &Tape {
+        &self.data
+    }
+
+    pub fn pointer(&self) -> usize {
+        self.ptr
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Program {
+    fn default() -> Self {
+        Program::new()
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::FailedToRead { pc } => {
+                write!(f, "failed to read input for instruction {}", pc)
+            }
+            InterpreterError::FailedToWrite { pc: Some(pc) } => {
+                write!(f, "failed to write output for instruction {}", pc)
+            }
+            InterpreterError::FailedToWrite { pc: None } => write!(f, "failed to flush output"),
+            InterpreterError::FailedToCompile(e) => write!(f, "{}", e),
+            InterpreterError::PointerOutOfBounds { pc, ptr } => write!(
+                f,
+                "instruction {} moved the pointer off the tape from cell {}",
+                pc, ptr
+            ),
+            InterpreterError::StepLimitExceeded => write!(f, "ran past the step limit"),
+            InterpreterError::Timeout { steps } => {
+                write!(f, "timed out after {} steps", steps)
+            }
+            InterpreterError::OutputLimitExceeded { pc, bytes } => write!(
+                f,
+                "instruction {} went over the output limit after {} bytes",
+                pc, bytes
+            ),
+            InterpreterError::Interrupted { steps } => {
+                write!(f, "interrupted after {} steps", steps)
+            }
+            InterpreterError::LoopDepthExceeded { pc } => {
+                write!(f, "the loop at instruction {} nests too deep", pc)
+            }
+            InterpreterError::Breakpoint { pc } => {
+                write!(f, "stopped at the breakpoint at instruction {}", pc)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CompileError {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InterpreterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InterpreterError::FailedToCompile(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CompileError> for InterpreterError {
+    fn from(e: CompileError) -> Self {
+        InterpreterError::FailedToCompile(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Program {
+    pub fn new() -> Program {
+        Program::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Program {
+        Program {
+            instructions: vec![Instruction::default()],
+            stack: Stack::with_limit(Vec::new(), config.stack_size),
+            config,
+            data: Tape::Dense(Vec::new()),
+            ptr: 0,
+            pc: 0,
+            depth: 0,
+            profile: None,
+            input: None,
+        }
+    }
+
+    /// A program whose [`Program::execute`] takes its input from `input`
+    /// instead of stdin.
+    pub fn with_input(config: Config, input: impl BfInput + 'static) -> Program {
+        Program {
+            input: Some(Box::new(input)),
+            ..Program::with_config(config)
+        }
+    }
+
+    /// Compiles and executes `source` in one go.
+    pub fn run(source: &str) -> Result<(), InterpreterError> {
+        let mut prog = Program::new();
+
+        prog.compile(source)?;
+        prog.execute().map(|_| ())
+    }
+
+    /// Checks that the brackets in `source` balance and nest no deeper than the
+    /// default bracket stack allows, without compiling anything.
+    pub fn validate(source: &str) -> Result<(), CompileError> {
+        let mut open: Vec<(usize, usize)> = Vec::new();
+
+        for (line, column, c) in source_positions(source) {
+            match c {
+                '[' if open.len() == STACK_SIZE => {
+                    return Err(CompileError::StackOverflow {
+                        line,
+                        column,
+                        limit: STACK_SIZE,
+                    })
+                }
+                '[' => open.push((line, column)),
+                ']' if open.pop().is_none() => {
+                    return Err(CompileError::UnmatchedBracket { line, column })
+                }
+                _ => (),
+            }
+        }
+
+        match open.pop() {
+            Some((line, column)) => Err(CompileError::UnclosedBracket { line, column }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn compile(&mut self, source: &str) -> Result<(), CompileError> {
+        // Brackets left open by an earlier failed compile would otherwise be matched here.
+        self.stack = Stack::with_limit(Vec::new(), self.config.stack_size);
+        self.pc = 0;
+        self.depth = 0;
+        // Every instruction comes from an operator, so there are never more than
+        // there are of them however much of the source is comments.
+        let debug_ops = self.config.debug_ops;
+        let operators = source
+            .chars()
+            .filter(|&c| {
+                matches!(c, '>' | '<' | '+' | '-' | '.' | ',' | '[' | ']')
+                    || (debug_ops && matches!(c, '#' | '@'))
+            })
+            .count();
+
+        self.instructions =
+            vec![Instruction::default(); self.config.program_size.min(operators) + 1];
+
+        embedded::compile(
+            source,
+            &mut self.instructions,
+            &mut self.stack,
+            &self.config,
+        )
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The compiled instructions, up to but not including the End.
+    pub fn instructions(&self) -> &[Instruction] {
+        let end = self
+            .instructions
+            .iter()
+            .position(|instruction| instruction.operator == Op::End)
+            .unwrap_or(self.instructions.len());
+
+        &self.instructions[..end]
+    }
+
+    /// The tape as it was left by the last call to [`Program::execute`].
+    pub fn tape(&self) -> &Tape {
+        &self.data
+    }
+
+    /// Where the data pointer was left by the last call to [`Program::execute`].
+    pub fn pointer(&self) -> usize {
+        self.ptr
+    }
+
+    /// The instruction the last call to [`Program::execute`] stopped at, which
+    /// is the one that failed if it did.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The cells within `radius` of the pointer, with the current one in
+    /// brackets, like the debugger's `p` shows them.
+    pub fn tape_window(&self, radius: usize) -> String {
+        tape_window(&self.data, self.ptr, radius, &self.config)
+    }
+
+    /// Counts from the last call to [`Program::execute`] when profiling is enabled.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Executes the compiled program against stdin, or the input from
+    /// [`Program::with_input`], and stdout.
+    pub fn execute(&mut self) -> Result<RunStats, InterpreterError> {
+        let mut out = BufWriter::new(io::stdout().lock());
+
+        self.reading(|prog, input| prog.execute_with(input, &mut out))
+    }
+
+    /// Runs exactly one instruction from where the program is, for a debugger
+    /// that drives it from outside. Input comes from the same place as for
+    /// [`Program::execute`], output comes back in the [`StepResult`].
+    ///
+    /// Stepping works on the same tape, pointer and `pc` as
+    /// [`Program::resume_with`], so [`Program::snapshot`] and
+    /// [`Program::restore`] work in between steps, and [`Program::reset`]
+    /// starts over. [`Config::max_steps`] doesn't count across steps, and a
+    /// scan takes a step for every move.
+    ///
+    /// [`Program::execute`] deliberately doesn't loop over this: stopping and
+    /// setting up again after every instruction would slow every run down.
+    /// Both go through the same interpreter, just with a different step limit,
+    /// so stepping to the end leaves the same output and tape.
+    pub fn step(&mut self) -> Result<StepResult, InterpreterError> {
+        if self.data.is_empty() {
+            self.reset();
+        }
+
+        let mut output = Vec::new();
+        let result = self.reading(|prog, input| prog.interpret(input, &mut output, 1));
+
+        match result {
+            Ok(_) | Err(InterpreterError::StepLimitExceeded) => Ok(StepResult {
+                ended: self.instructions[self.pc].operator.stops(),
+                pc: self.pc,
+                output,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Calls `f` with the input from [`Program::with_input`], or stdin.
+    fn reading<T>(&mut self, f: impl FnOnce(&mut Program, &mut dyn Read) -> T) -> T {
+        match self.input.take() {
+            Some(mut input) => {
+                let result = f(self, &mut input::Reader(&mut input));
+
+                self.input = Some(input);
+                result
+            }
+            // Stdin is left unlocked so the debugger can still read its commands.
+            None => f(self, &mut io::stdin()),
+        }
+    }
+
+    /// Executes the compiled program with all of its input up front, returning
+    /// everything it wrote instead of touching stdin or stdout.
+    pub fn run_with_io(&mut self, input: &[u8]) -> Result<Vec<u8>, InterpreterError> {
+        let mut output = Vec::new();
+
+        self.execute_with(&mut &input[..], &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Executes the compiled program, reading `,` from `input` and writing `.` to `out`.
+    pub fn execute_with(
+        &mut self,
+        input: &mut dyn Read,
+        out: &mut dyn Write,
+    ) -> Result<RunStats, InterpreterError> {
+        self.reset();
+        self.resume_with(input, out)
+    }
+
+    /// Puts the tape, pointer and program counter back to how a fresh run starts.
+    pub fn reset(&mut self) {
+        self.data = Tape::filled(
+            self.config.tape_size.min(self.config.max_tape).max(1),
+            self.config.sparse,
+            self.config.fill(),
+        );
+        self.ptr = 0;
+        self.pc = 0;
+        self.depth = 0;
+    }
+
+    /// Captures the tape, pointer and program counter, so a run that stopped
+    /// early can be picked up again with [`Program::restore`].
+    pub fn snapshot(&self) -> State {
+        State {
+            data: self.data.clone(),
+            ptr: self.ptr,
+            pc: self.pc,
+            depth: self.depth,
+        }
+    }
+
+    /// Puts back a [`State`] from [`Program::snapshot`], for the next
+    /// [`Program::resume_with`] to carry on from.
+    ///
+    /// The state only makes sense for the same compiled program it came from.
+    pub fn restore(&mut self, state: State) {
+        self.data = state.data;
+        self.ptr = state.ptr;
+        self.pc = state.pc;
+        self.depth = state.depth;
+    }
+
+    /// Like [`Program::execute_with`], but carries on from the tape, pointer
+    /// and instruction the last run stopped at instead of starting over.
+    ///
+    /// A run that finished stays finished until the program is recompiled or
+    /// [`Program::reset`].
+    pub fn resume_with(
+        &mut self,
+        input: &mut dyn Read,
+        out: &mut dyn Write,
+    ) -> Result<RunStats, InterpreterError> {
+        if self.data.is_empty() {
+            self.reset();
+        }
+
+        self.profile = self.config.profile.then(|| Profile {
+            loop_entries: vec![0; self.instructions.len()],
+            ..Profile::default()
+        });
+
+        let start = Instant::now();
+        let max_steps = self.config.max_steps.unwrap_or(u64::MAX);
+        let result = self.interpret(input, out, max_steps);
+
+        if let Some(profile) = self.profile.as_mut() {
+            profile.elapsed = start.elapsed();
+        }
+
+        // Whatever the program wrote before an error still gets out.
+        let flushed = out
+            .flush()
+            .map_err(|_| InterpreterError::FailedToWrite { pc: None });
+
+        result.and_then(|stats| flushed.map(|_| stats))
+    }
+
+    /// Runs until the end or for `max_steps` instructions, whichever comes first.
+    fn interpret(
+        &mut self,
+        input: &mut dyn Read,
+        out: &mut dyn Write,
+        max_steps: u64,
+    ) -> Result<RunStats, InterpreterError> {
+        let data = &mut self.data;
+        let ptr = &mut self.ptr;
+        let profile = &mut self.profile;
+        let mask = self.config.cell_width.mask();
+        let pc = &mut self.pc;
+        let mut stepping = self.config.debug;
+        let deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
+        let mut steps: u64 = 0;
+        let mut output_bytes: u64 = 0;
+        let mut max_ptr = *ptr;
+        let mut max_value = read_cell(data, *ptr, *pc)?;
+        let max_loop_depth = self.config.max_loop_depth.unwrap_or(usize::MAX);
+        let depth = &mut self.depth;
+        let mut condition_held = false;
+
+        if self.config.dispatch == Dispatch::Table
+            && !(self.config.trace
+                || self.config.debug
+                || self.config.profile
+                || self.config.dump_every.is_some()
+                || self.config.break_at.is_some()
+                || self.config.break_when.is_some())
+        {
+            return dispatch::interpret(
+                &self.instructions,
+                data,
+                ptr,
+                pc,
+                depth,
+                max_steps,
+                &self.config,
+                input,
+                out,
+            );
+        }
+
+        while !self.instructions[*pc].operator.stops() {
+            check_limits(steps, max_steps, deadline, &self.config)?;
+
+            let mut breaking = self.config.break_at == Some(*pc);
+
+            if let Some(condition) = self.config.break_when {
+                // Cells the tape hasn't grown to yet still hold the fill value.
+                let value = match condition.cell < data.len() {
+                    true => self.config.cell_value(data[condition.cell]),
+                    false => self.config.cell_value(self.config.fill()),
+                };
+                let holds = condition.holds(value);
+
+                breaking |= holds && !condition_held;
+                condition_held = holds;
+            }
+
+            if breaking {
+                if self.config.break_exit {
+                    return Err(InterpreterError::Breakpoint { pc: *pc });
+                }
+
+                eprintln!("breakpoint at instruction {}", *pc);
+                stepping = true;
+            }
+
+            steps += 1;
+
+            let operand = self.instructions[*pc].operand;
+            let cell = read_cell(data, *ptr, *pc)?;
+
+            if let Some(profile) = profile.as_mut() {
+                let operator = self.instructions[*pc].operator;
+
+                profile.op_counts[operator as usize] += 1;
+
+                if operator == Op::JmpFwd && cell != 0 {
+                    profile.loop_entries[*pc] += 1;
+                }
+            }
+
+            if self.config.trace {
+                let instruction = &self.instructions[*pc];
+
+                eprintln!(
+                    "{} pc {:04} {:?} {} ptr {} = {}",
+                    steps,
+                    *pc,
+                    instruction.operator,
+                    instruction.operand,
+                    *ptr,
+                    self.config.cell_value(cell)
+                );
+            }
+
+            if stepping {
+                out.flush()
+                    .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+
+                match debug_prompt(*pc, &self.instructions[*pc], data, *ptr, &self.config) {
+                    DebugCommand::Step => (),
+                    DebugCommand::Continue => stepping = false,
+                    DebugCommand::Quit => break,
+                }
+            }
+
+            // A loop optimizing folded away would still have been entered, so
+            // it counts for the limit the same as the loop would.
+            if self.instructions[*pc].operator.folds_loop() && cell != 0 && *depth == max_loop_depth
+            {
+                return Err(InterpreterError::LoopDepthExceeded { pc: *pc });
+            }
+
+            match self.instructions[*pc].operator {
+                Op::IncDp => {
+                    *ptr = move_right(data, *ptr, operand, &self.config)
+                        .ok_or(InterpreterError::PointerOutOfBounds { pc: *pc, ptr: *ptr })?
+                }
+                Op::DecDp => {
+                    *ptr = move_left(data, *ptr, operand, &self.config)
+                        .ok_or(InterpreterError::PointerOutOfBounds { pc: *pc, ptr: *ptr })?
+                }
+                Op::IncVal => {
+                    write_cell(data, *ptr, *pc, cell.wrapping_add(operand as u32) & mask)?
+                }
+                Op::DecVal => {
+                    write_cell(data, *ptr, *pc, cell.wrapping_sub(operand as u32) & mask)?
+                }
+                Op::Out => {
+                    let (bytes, len) = self.config.output_bytes(cell);
+
+                    if self.config.over_output_limit(output_bytes, len) {
+                        return Err(InterpreterError::OutputLimitExceeded {
+                            pc: *pc,
+                            bytes: output_bytes,
+                        });
+                    }
+
+                    out.write_all(&bytes[..len])
+                        .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+                    output_bytes += len as u64;
+
+                    if self.config.unbuffered {
+                        out.flush()
+                            .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+                    }
+                }
+                Op::In => {
+                    // Make sure any prompt the program printed is visible before blocking.
+                    out.flush()
+                        .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+
+                    let value = match read_input(input, &self.config) {
+                        Ok(Some(value)) => value,
+                        Ok(None) => match self.config.eof {
+                            EofMode::Zero => 0,
+                            EofMode::NegativeOne => mask,
+                            EofMode::Unchanged => cell,
+                        },
+                        Err(()) => return Err(InterpreterError::FailedToRead { pc: *pc }),
+                    };
+
+                    write_cell(data, *ptr, *pc, value)?
+                }
+                Op::Clear => write_cell(data, *ptr, *pc, 0)?,
+                Op::SetConst => write_cell(data, *ptr, *pc, operand as u32 & mask)?,
+                Op::ScanRight => {
+                    let mut first = true;
+
+                    while read_cell(data, *ptr, *pc)? != 0 {
+                        // Every move after the first is a step of its own, or a scan
+                        // round a tape with no zero cell on it would never stop.
+                        if !first {
+                            check_limits(steps, max_steps, deadline, &self.config)?;
+                            steps += 1;
+                        }
+
+                        first = false;
+
+                        if operand == 1 {
+                            // Skip to the last non-zero cell of the run, the move steps off it.
+                            *ptr = data.next_zero(*ptr) - 1;
+                        }
+
+                        *ptr = move_right(data, *ptr, operand, &self.config)
+                            .ok_or(InterpreterError::PointerOutOfBounds { pc: *pc, ptr: *ptr })?;
+                    }
+                }
+                Op::ScanLeft => {
+                    let mut first = true;
+
+                    while read_cell(data, *ptr, *pc)? != 0 {
+                        if !first {
+                            check_limits(steps, max_steps, deadline, &self.config)?;
+                            steps += 1;
+                        }
+
+                        first = false;
+
+                        if operand == 1 {
+                            *ptr = data.previous_zero(*ptr).map_or(0, |zero| zero + 1);
+                        }
+
+                        *ptr = move_left(data, *ptr, operand, &self.config)
+                            .ok_or(InterpreterError::PointerOutOfBounds { pc: *pc, ptr: *ptr })?;
+                    }
+                }
+                Op::Dump => {
+                    out.flush()
+                        .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+                    eprintln!(
+                        "# pc {:04} | {}",
+                        *pc,
+                        tape_window(data, *ptr, 8, &self.config)
+                    );
+                }
+                // Jumping back lands on the `[` and steps past it, so only
+                // entering a loop from above passes through here.
+                Op::JmpFwd => {
+                    if cell == 0 {
+                        *pc = self.instructions[*pc].operand
+                    } else if *depth == max_loop_depth {
+                        return Err(InterpreterError::LoopDepthExceeded { pc: *pc });
+                    } else {
+                        *depth += 1;
+                    }
+                }
+                Op::JmpBck => {
+                    if cell != 0 {
+                        *pc = self.instructions[*pc].operand
+                    } else {
+                        *depth -= 1;
+                    }
+                }
+                Op::End | Op::Halt => unreachable!("the loop stops before End and Halt"),
+            }
+            // Every write lands on the cell under the pointer, so this sees them all.
+            max_ptr = max_ptr.max(*ptr);
+            max_value = max_value.max(read_cell(data, *ptr, *pc)?);
+
+            // pc += 1;
+            *pc = pc.wrapping_add(1);
+
+            if self
+                .config
+                .dump_every
+                .is_some_and(|every| steps.is_multiple_of(every))
+            {
+                out.flush()
+                    .map_err(|_| InterpreterError::FailedToWrite { pc: Some(*pc) })?;
+                eprintln!(
+                    "{} pc {:04} | {}",
+                    steps,
+                    *pc,
+                    tape_window(data, *ptr, 8, &self.config)
+                );
+            }
+        }
+
+        Ok(RunStats {
+            steps,
+            output_bytes,
+            final_ptr: *ptr,
+            max_ptr,
+            max_value,
+            halted: self.instructions[*pc].operator == Op::Halt,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{source_positions, strip_bom, Op, Program};
+use core::fmt;
+
+/// Something that compiles fine but is probably not what was meant, from
+/// [`Program::lint`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lint {
+    /// Two instructions in a row, like `+-` or `<>`, that undo each other. The
+    /// position is the first of the two.
+    CancelsOut {
+        ops: &'static str,
+        line: usize,
+        column: usize,
+    },
+    /// A `[]` with nothing inside, which never ends if the cell isn't zero
+    /// when it gets there. The position is the `[`.
+    EmptyLoop { line: usize, column: usize },
+}
+
+impl Lint {
+    /// The line and column in the source the lint points at.
+    pub fn position(&self) -> (usize, usize) {
+        match *self {
+            Lint::CancelsOut { line, column, .. } | Lint::EmptyLoop { line, column } => {
+                (line, column)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::CancelsOut { ops, .. } => write!(f, "'{}' does nothing", ops),
+            Lint::EmptyLoop { .. } => write!(f, "'[]' never ends unless the cell is already 0"),
+        }
+    }
+}
+
+impl Program {
+    /// Looks through `source` for instructions that cancel each other out and
+    /// for empty loops.
+    ///
+    /// Comments in between don't stop a pair from counting, anything else
+    /// does, so `[ ]` is as empty as `[]`. With [`PointerPolicy::Clamp`](crate::PointerPolicy::Clamp) a `<>`
+    /// at the start of the tape does still move the pointer, and with
+    /// [`PointerPolicy::Error`](crate::PointerPolicy::Error) it can still fail,
+    /// so those are only a hint.
+    pub fn lint(source: &str) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        let mut last: Option<(Op, usize, usize)> = None;
+
+        for (line, column, c) in source_positions(strip_bom(source)) {
+            let op = match Op::from_char(c) {
+                Some(op) => op,
+                None => continue,
+            };
+            let lint = last.and_then(|(previous, line, column)| {
+                let ops = match (previous, op) {
+                    (Op::IncVal, Op::DecVal) => "+-",
+                    (Op::DecVal, Op::IncVal) => "-+",
+                    (Op::IncDp, Op::DecDp) => "><",
+                    (Op::DecDp, Op::IncDp) => "<>",
+                    (Op::JmpFwd, Op::JmpBck) => return Some(Lint::EmptyLoop { line, column }),
+                    _ => return None,
+                };
+
+                Some(Lint::CancelsOut { ops, line, column })
+            });
+
+            last = match lint {
+                Some(lint) => {
+                    lints.push(lint);
+                    None
+                }
+                None => Some((op, line, column)),
+            };
+        }
+
+        lints
+    }
+}
@@ -1,253 +1,1387 @@
-use core::panic;
-use std::array;
+use mindsuck::{
+    CellWidth, CompileError, Condition, Config, Dialect, Dispatch, Endian, EofMode, Instruction,
+    InterpreterError, Op, OutputEncoding, Passes, PointerPolicy, Profile, Program,
+};
+use std::fmt;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, BufReader, BufWriter, IsTerminal, Read, Write};
 use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-#[derive(PartialEq, Clone, Debug)]
-enum Op {
-    End,
-    IncDp,
-    DecDp,
-    IncVal,
-    DecVal,
-    Out,
-    In,
-    JmpFwd,
-    JmpBck,
+// What the process exits with when it fails, so scripts can tell why. Anything
+// else, like a bad option or a file that can't be read, is 1.
+const EXIT_COMPILE: i32 = 2;
+const EXIT_RUNTIME: i32 = 3;
+const EXIT_LIMIT: i32 = 4;
+const EXIT_BREAKPOINT: i32 = 5;
+// What a shell reports for a process killed by SIGINT.
+const EXIT_INTERRUPTED: i32 = 130;
+
+fn exit_code(e: &InterpreterError) -> i32 {
+    match e {
+        InterpreterError::FailedToCompile(_) => EXIT_COMPILE,
+        InterpreterError::FailedToRead { .. }
+        | InterpreterError::FailedToWrite { .. }
+        | InterpreterError::PointerOutOfBounds { .. } => EXIT_RUNTIME,
+        InterpreterError::StepLimitExceeded
+        | InterpreterError::Timeout { .. }
+        | InterpreterError::LoopDepthExceeded { .. }
+        | InterpreterError::OutputLimitExceeded { .. } => EXIT_LIMIT,
+        InterpreterError::Breakpoint { .. } => EXIT_BREAKPOINT,
+        InterpreterError::Interrupted { .. } => EXIT_INTERRUPTED,
+    }
 }
 
-enum Statuses {
-    Success,
-    Failure,
+/// Set on Ctrl-C, every run's [`Config::interrupt`] points here.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl-C stop the run where it is instead of killing the process, so
+/// there's still a chance to say how far it got. A second Ctrl-C gets out
+/// straight away, for a run stuck waiting on input.
+#[cfg(unix)]
+fn catch_interrupts() {
+    const SIGINT: i32 = 2;
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+
+    extern "C" fn on_interrupt(_: i32) {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            // Only async-signal-safe functions can be called from a handler.
+            unsafe { _exit(EXIT_INTERRUPTED) }
+        }
+    }
+
+    unsafe {
+        signal(SIGINT, on_interrupt);
+    }
 }
 
-const PROGRAM_SIZE: u16 = 4096;
-const STACK_SIZE: u16 = 512;
-const DATA_SIZE: u16 = 65535;
+#[cfg(not(unix))]
+fn catch_interrupts() {}
+
+/// When to color diagnostics on stderr.
+#[derive(Clone, Copy, PartialEq)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
 
-#[derive(Debug)]
-enum StackErrors {
-    OverFlow,
-    UnderFlow,
+/// Where `,` gets its bytes from, stdin unless one of these is given.
+enum InputSource {
+    File(String),
+    Data(String),
 }
 
-struct Stack {
-    ptr: u32,
-    arr: [u16; STACK_SIZE as usize],
+struct Args {
+    files: Vec<String>,
+    color: ColorChoice,
+    config: Config,
+    dump_tape: Option<usize>,
+    emit_c: bool,
+    emit_rust: bool,
+    input: Option<InputSource>,
+    output_file: Option<String>,
+    repl: bool,
+    final_newline: bool,
+    dialect: Dialect,
+    max_program_bytes: Option<u64>,
+    disasm: bool,
+    explain: bool,
+    histogram: bool,
+    check: bool,
+    lint: bool,
+    debug_on_error: bool,
+    bench: bool,
+    prompt: bool,
+    show_codes: bool,
+    self_input: bool,
+    compile_to: Option<String>,
+    run_compiled: Option<String>,
+    repeat: u32,
+    quiet: bool,
+    verbose: bool,
 }
 
-impl Stack {
-    pub fn new() -> Stack {
-        Stack {
-            ptr: 0,
-            arr: [0; STACK_SIZE as usize],
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut files = Vec::new();
+    let mut color = ColorChoice::Auto;
+    let mut config = Config::default();
+    let mut dump_tape = None;
+    let mut emit_c = false;
+    let mut emit_rust = false;
+    let mut input = None;
+    let mut output_file = None;
+    let mut repl = false;
+    let mut final_newline = false;
+    let mut dialect = Dialect::default();
+    let mut max_program_bytes = None;
+    let mut disasm = false;
+    let mut explain = false;
+    let mut histogram = false;
+    let mut check = false;
+    let mut lint = false;
+    let mut debug_on_error = false;
+    let mut bench = false;
+    let mut prompt = false;
+    let mut show_codes = false;
+    let mut self_input = false;
+    let mut wrap_tape = false;
+    let mut compile_to = None;
+    let mut run_compiled = None;
+    let mut repeat = 1;
+    let mut quiet = false;
+    let mut verbose = false;
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--cell-width" => {
+                let value = iter.next().ok_or("--cell-width expects a bit width")?;
+
+                config.cell_width =
+                    value
+                        .parse()
+                        .ok()
+                        .and_then(CellWidth::from_bits)
+                        .ok_or(format!(
+                            "invalid cell width '{}', expected 8, 16 or 32",
+                            value
+                        ))?;
+            }
+            "--wide-input" => config.wide_input = true,
+            "--input-endian" => {
+                let value = iter.next().ok_or("--input-endian expects big or little")?;
+
+                config.input_endian = Endian::from_name(value)
+                    .ok_or_else(|| format!("invalid byte order '{}'", value))?;
+            }
+            "--wide-output" => config.wide_output = true,
+            "--numeric-io" => config.numeric_io = true,
+            "--output-endian" => {
+                let value = iter.next().ok_or("--output-endian expects big or little")?;
+
+                config.output_endian = Endian::from_name(value)
+                    .ok_or_else(|| format!("invalid byte order '{}'", value))?;
+            }
+            "--out-encoding" => {
+                let value = iter
+                    .next()
+                    .ok_or("--out-encoding expects bytes, utf8 or latin1")?;
+
+                config.output_encoding = OutputEncoding::from_name(value).ok_or(format!(
+                    "invalid output encoding '{}', expected bytes, utf8 or latin1",
+                    value
+                ))?;
+            }
+            "--eof" => {
+                let value = iter.next().ok_or("--eof expects a mode")?;
+
+                config.eof = EofMode::from_name(value).ok_or(format!(
+                    "invalid eof mode '{}', expected zero, minusone or unchanged",
+                    value
+                ))?;
+            }
+            "--init-cell" => {
+                let value = iter.next().ok_or("--init-cell expects a number")?;
+
+                // Negative numbers are what `--signed` would show, so they wrap like `-` does.
+                config.init_cell = value
+                    .parse()
+                    .or_else(|_| value.parse::<i32>().map(|n| n as u32))
+                    .map_err(|_| format!("invalid cell value '{}'", value))?;
+            }
+            "--tape-size" => {
+                let value = iter.next().ok_or("--tape-size expects a number of cells")?;
+
+                config.tape_size = value
+                    .parse()
+                    .map_err(|_| format!("invalid tape size '{}'", value))?;
+            }
+            "--max-tape" => {
+                let value = iter.next().ok_or("--max-tape expects a number of cells")?;
+
+                // A tape with no cells has nowhere for the pointer to be.
+                config.max_tape = value
+                    .parse()
+                    .ok()
+                    .filter(|&cells| cells > 0)
+                    .ok_or_else(|| format!("invalid max tape size '{}'", value))?;
+            }
+            "--sparse" => config.sparse = true,
+            "--dispatch" => {
+                let value = iter.next().ok_or("--dispatch expects match or table")?;
+
+                config.dispatch = Dispatch::from_name(value)
+                    .ok_or_else(|| format!("invalid dispatch '{}'", value))?;
+            }
+            "--pointer" => {
+                let value = iter.next().ok_or("--pointer expects a policy")?;
+
+                config.pointer = PointerPolicy::from_name(value).ok_or(format!(
+                    "invalid pointer policy '{}', expected error, wrap or clamp",
+                    value
+                ))?;
+            }
+            "--color" => {
+                let value = iter.next().ok_or("--color expects a mode")?;
+
+                color = ColorChoice::from_name(value).ok_or(format!(
+                    "invalid color mode '{}', expected auto, always or never",
+                    value
+                ))?;
+            }
+            "--no-color" => color = ColorChoice::Never,
+            "--program-size" => {
+                let value = iter
+                    .next()
+                    .ok_or("--program-size expects a number of instructions")?;
+
+                config.program_size = value
+                    .parse()
+                    .map_err(|_| format!("invalid program size '{}'", value))?;
+            }
+            "--stack-size" => {
+                let value = iter
+                    .next()
+                    .ok_or("--stack-size expects a number of loops")?;
+
+                config.stack_size = value
+                    .parse()
+                    .map_err(|_| format!("invalid stack size '{}'", value))?;
+            }
+            "--dialect" => {
+                let value = iter.next().ok_or("--dialect expects a language")?;
+
+                dialect = Dialect::from_name(value).ok_or(format!(
+                    "invalid dialect '{}', expected bf, ook or short-ook",
+                    value
+                ))?;
+            }
+            "--optimize" => config.optimize = Passes::ALL,
+            "--unbuffered" => config.unbuffered = true,
+            "--debug" => config.debug = true,
+            "--profile" => config.profile = true,
+            "--trace" => config.trace = true,
+            "--signed" => config.signed = true,
+            "--strict" => config.strict = true,
+            "--debug-ops" => config.debug_ops = true,
+            "--emit-c" => emit_c = true,
+            "--emit-rust" => emit_rust = true,
+            "--disasm" => disasm = true,
+            "--explain" => {
+                disasm = true;
+                explain = true;
+            }
+            "--histogram" => histogram = true,
+            "--bench" => bench = true,
+            "--prompt" => prompt = true,
+            "--show-codes" => show_codes = true,
+            "--self-input" => self_input = true,
+            "--wrap-tape" => wrap_tape = true,
+            "--quiet" => quiet = true,
+            "--verbose" => verbose = true,
+            "--check" => check = true,
+            "--lint" => lint = true,
+            "--debug-on-error" => debug_on_error = true,
+            "--repl" => repl = true,
+            "--final-newline" => final_newline = true,
+            "--break-at" => {
+                let value = iter
+                    .next()
+                    .ok_or("--break-at expects an instruction index")?;
+
+                config.break_at = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid instruction index '{}'", value))?,
+                );
+            }
+            "--break-when" => {
+                let value = iter.next().ok_or("--break-when expects a condition")?;
+
+                config.break_when = Some(Condition::parse(value).ok_or(format!(
+                    "invalid condition '{}', expected something like cell0==42",
+                    value
+                ))?);
+            }
+            "--break-exit" => config.break_exit = true,
+            "--dump-every" => {
+                let value = iter
+                    .next()
+                    .ok_or("--dump-every expects a number of steps")?;
+
+                config.dump_every = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&every| every > 0)
+                        .ok_or(format!("invalid number of steps '{}'", value))?,
+                );
+            }
+            "--max-steps" => {
+                let value = iter.next().ok_or("--max-steps expects a number of steps")?;
+
+                config.max_steps = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of steps '{}'", value))?,
+                );
+            }
+            "--repeat" => {
+                let value = iter.next().ok_or("--repeat expects a number of runs")?;
+
+                repeat = value
+                    .parse()
+                    .ok()
+                    .filter(|&runs| runs > 0)
+                    .ok_or_else(|| format!("invalid number of runs '{}'", value))?;
+            }
+            "--timeout-ms" => {
+                let value = iter
+                    .next()
+                    .ok_or("--timeout-ms expects a number of milliseconds")?;
+
+                config.timeout = Some(Duration::from_millis(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid timeout '{}'", value))?,
+                ));
+            }
+            "--max-loop-depth" => {
+                let value = iter
+                    .next()
+                    .ok_or("--max-loop-depth expects a number of loops")?;
+
+                config.max_loop_depth = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid loop depth '{}'", value))?,
+                );
+            }
+            "--max-output-bytes" => {
+                let value = iter
+                    .next()
+                    .ok_or("--max-output-bytes expects a number of bytes")?;
+
+                config.max_output = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of bytes '{}'", value))?,
+                );
+            }
+            "--max-program-bytes" => {
+                let value = iter
+                    .next()
+                    .ok_or("--max-program-bytes expects a number of bytes")?;
+
+                max_program_bytes = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of bytes '{}'", value))?,
+                );
+            }
+            "--input-file" => {
+                input = Some(InputSource::File(
+                    iter.next().ok_or("--input-file expects a path")?.clone(),
+                ));
+            }
+            "--input" => {
+                input = Some(InputSource::Data(
+                    iter.next().ok_or("--input expects the input data")?.clone(),
+                ));
+            }
+            "--output-file" => {
+                output_file = Some(iter.next().ok_or("--output-file expects a path")?.clone());
+            }
+            "--compile-to" => {
+                compile_to = Some(iter.next().ok_or("--compile-to expects a path")?.clone());
+            }
+            "--run-compiled" => {
+                run_compiled = Some(iter.next().ok_or("--run-compiled expects a path")?.clone());
+            }
+            "--dump-tape" => {
+                let value = iter.next().ok_or("--dump-tape expects a number of cells")?;
+
+                dump_tape = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid number of cells '{}'", value))?,
+                );
+            }
+            _ => files.push(arg.clone()),
         }
     }
 
-    pub fn push(&mut self, a: u16) -> Result<(), StackErrors> {
-        if self.ptr >= STACK_SIZE.into() {
-            return Err(StackErrors::OverFlow);
+    // Ook! is all `!`s, there would be no program left before the first one.
+    if self_input && dialect != Dialect::Brainfuck {
+        return Err("--self-input only works with --dialect bf".to_owned());
+    }
+
+    // Done last so `--tape-size` counts wherever it is on the command line.
+    if wrap_tape {
+        if config.tape_size == 0 {
+            return Err("--wrap-tape needs a --tape-size of at least 1".to_owned());
+        }
+
+        config.pointer = PointerPolicy::Wrap;
+        config.max_tape = config.tape_size;
+    }
+
+    Ok(Args {
+        files,
+        color,
+        config,
+        dump_tape,
+        emit_c,
+        emit_rust,
+        input,
+        output_file,
+        repl,
+        final_newline,
+        dialect,
+        max_program_bytes,
+        disasm,
+        explain,
+        histogram,
+        check,
+        lint,
+        debug_on_error,
+        bench,
+        prompt,
+        show_codes,
+        self_input,
+        compile_to,
+        run_compiled,
+        repeat,
+        quiet,
+        verbose,
+    })
+}
+
+fn main() {
+    let args = std::env::args().collect::<Vec<String>>();
+
+    let mut parsed = match parse_args(&args[1..]) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}: {}", args[0], message);
+            process::exit(1);
+        }
+    };
+
+    parsed.config.interrupt = Some(&INTERRUPTED);
+
+    if parsed.repl {
+        repl(&parsed);
+        return;
+    }
+
+    if let Some(path) = &parsed.run_compiled {
+        let mut prog = Program::with_config(parsed.config.clone());
+
+        if let Err(e) =
+            File::open(path).and_then(|file| prog.load_compiled(&mut BufReader::new(file)))
+        {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+
+        match parsed.disasm {
+            true => disassemble(&prog, parsed.explain),
+            false => run(&mut prog, &parsed, path),
+        }
+
+        return;
+    }
+
+    // No file or `-` reads the program from stdin, which leaves `,` at EOF.
+    let names = match parsed.files.is_empty() {
+        true => vec!["-".to_owned()],
+        false => parsed.files.clone(),
+    };
+
+    let missing = names
+        .iter()
+        .filter(|name| *name != "-" && !Path::new(name).exists())
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        for name in missing {
+            eprintln!("{}: no such file", name);
+        }
+
+        eprintln!(
+            "Usage: {} [--color auto|always|never] [--cell-width 8|16|32] [--signed] [--wide-input] [--input-endian big|little] [--wide-output] [--output-endian big|little] [--numeric-io] [--out-encoding bytes|utf8|latin1] [--eof zero|minusone|unchanged] [--init-cell N] [--tape-size N] [--max-tape N] [--sparse] [--pointer error|wrap|clamp] [--wrap-tape] [--program-size N] [--stack-size N] [--dialect bf|ook|short-ook] [--strict] [--optimize] [--unbuffered] [--dispatch table|match] [--final-newline] [--debug] [--debug-ops] [--debug-on-error] [--break-at N] [--break-when cellN==V] [--break-exit] [--profile] [--trace] [--dump-every N] [--max-steps N] [--repeat N] [--timeout-ms N] [--max-loop-depth N] [--max-program-bytes N] [--max-output-bytes N] [--input-file path] [--input data] [--self-input] [--prompt] [--output-file path] [--show-codes] [--quiet] [--verbose] [--dump-tape N] [--emit-c] [--emit-rust] [--disasm] [--explain] [--histogram] [--compile-to path] [--run-compiled path] [--bench] [--check] [--lint] [--repl] [filename|-]...\n",
+            args[0]
+        );
+        process::exit(1);
+    }
+
+    // Every file is compiled as one program, `sources` remembers the line each one starts on.
+    let mut buffer = String::new();
+    let mut sources: Vec<(&str, usize)> = Vec::new();
+    let limit = parsed.max_program_bytes.unwrap_or(u64::MAX);
+    let mut remaining = limit;
+
+    for name in names.iter() {
+        if !buffer.is_empty() && !buffer.ends_with('\n') {
+            buffer.push('\n');
+        }
+
+        let read = match name.as_str() {
+            "-" => {
+                sources.push(("<stdin>", buffer.lines().count() + 1));
+                read_source(io::stdin(), &mut buffer, remaining)
+            }
+            _ => {
+                sources.push((name, buffer.lines().count() + 1));
+                File::open(name).and_then(|file| read_source(file, &mut buffer, remaining))
+            }
+        }
+        .unwrap_or_else(|e| {
+            eprintln!("{}: {}", name, e);
+            process::exit(1);
+        });
+
+        remaining = match remaining.checked_sub(read) {
+            Some(remaining) => remaining,
+            None => {
+                eprintln!("{}: program is longer than {} bytes", name, limit);
+                process::exit(EXIT_LIMIT);
+            }
+        };
+    }
+
+    // Pragmas at the top of the program get the last word over the command line.
+    // Only Brainfuck has them, a line of Ook! can start with `!` too.
+    let mut config = parsed.config.clone();
+    let (mut buffer, warnings) = match parsed.dialect {
+        Dialect::Brainfuck => config.apply_pragmas(&buffer),
+        _ => (buffer, Vec::new()),
+    };
+
+    // Past the pragmas, everything after the first `!` is the program's input rather than more program.
+    if parsed.self_input {
+        if let Some(split) = buffer.find('!') {
+            parsed.input = Some(InputSource::Data(buffer[split + 1..].to_owned()));
+            buffer.truncate(split);
+        }
+    }
+
+    for warning in warnings {
+        eprintln!("{}: warning: {}", sources[0].0, warning);
+    }
+
+    let mut prog = Program::with_config(config);
+
+    let compiled = parsed
+        .dialect
+        .translate(&buffer)
+        .and_then(|source| prog.compile(&source).map(|_| source));
+    let color = parsed.color.enabled();
+    // Where `line` of the whole program is: the file it came from and the line in that file.
+    let locate = |line: usize| {
+        let (name, start) = sources
+            .iter()
+            .rev()
+            .find(|(_, start)| *start <= line)
+            .expect("Every line belongs to a source file");
+
+        (
+            *name,
+            line - start + 1,
+            buffer.lines().nth(line - 1).unwrap_or(""),
+        )
+    };
+
+    let source = match compiled {
+        Ok(source) => source,
+        Err(e) => {
+            match e.position() {
+                Some((line, column)) => {
+                    let (name, line, text) = locate(line);
+
+                    eprintln!("{}:{}:{}: {}", name, line, column, error_message(&e, color));
+                    eprint!("{}", snippet(text, line, column, color));
+
+                    let note = match e {
+                        CompileError::UnmatchedBracket { .. } => {
+                            "the last loop to close before it opened here"
+                        }
+                        _ => "this '[' is still open too",
+                    };
+                    // Translating worked the first time, and it keeps every bracket in place.
+                    let translated = parsed.dialect.translate(&buffer).unwrap_or_default();
+
+                    for (line, column) in e.related_brackets(&translated) {
+                        let (name, line, text) = locate(line);
+
+                        eprintln!("{}:{}:{}: note: {}", name, line, column, note);
+                        eprint!("{}", snippet(text, line, column, color));
+                    }
+                }
+                None => eprintln!("{}: {}", names.join(", "), error_message(&e, color)),
+            }
+
+            process::exit(EXIT_COMPILE);
+        }
+    };
+
+    if parsed.verbose && prog.config().optimize != Passes::NONE {
+        print_optimizations(&prog, &source);
+    }
+
+    if parsed.lint {
+        for lint in Program::lint(&source) {
+            let (line, column) = lint.position();
+            let (name, line, text) = locate(line);
+
+            eprintln!("{}:{}:{}: warning: {}", name, line, column, lint);
+            eprint!("{}", snippet(text, line, column, color));
+        }
+    }
+
+    if parsed.check {
+        eprintln!("{}: ok", names.join(", "));
+        return;
+    }
+
+    if parsed.emit_c {
+        prog.emit_c(&mut io::stdout())
+            .expect("Could not write the generated C");
+
+        return;
+    }
+
+    if parsed.disasm {
+        disassemble(&prog, parsed.explain);
+        return;
+    }
+
+    if parsed.histogram {
+        print_histogram(&source, prog.config().debug_ops);
+        return;
+    }
+
+    if parsed.bench {
+        bench(prog.config(), &source, &parsed.input);
+        return;
+    }
+
+    if parsed.emit_rust {
+        prog.emit_rust(&mut io::stdout())
+            .expect("Could not write the generated Rust");
+
+        return;
+    }
+
+    if let Some(path) = &parsed.compile_to {
+        let saved = File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+
+            prog.save_compiled(&mut out)?;
+            out.flush()
+        });
+
+        if let Err(e) = saved {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
         }
 
-        self.arr[self.ptr as usize] = a;
-        self.ptr += 1;
-        // self.ptr = self.ptr.wrapping_add(1);
+        return;
+    }
+
+    run(&mut prog, &parsed, &names.join(", "));
+}
+
+/// Runs a compiled program with everything the command line asked for around
+/// it, exiting with an error if it fails. `name` is what errors are reported
+/// against.
+fn run(prog: &mut Program, parsed: &Args, name: &str) {
+    catch_interrupts();
 
-        Ok(())
+    if parsed.repeat > 1 {
+        run_repeatedly(prog, parsed, name);
+        return;
     }
 
-    pub fn pop(&mut self) -> Result<u16, StackErrors> {
-        if self.ptr == 0 {
-            return Err(StackErrors::UnderFlow);
+    let mut output = LastByte {
+        inner: BufWriter::new(open_output(parsed)),
+        last: b'\n',
+    };
+    let mut result = prog.execute_with(&mut open_input(&parsed.input, parsed.prompt), &mut output);
+
+    if parsed.final_newline && output.last != b'\n' {
+        let written = output.write_all(b"\n").and_then(|_| output.flush());
+
+        // Failing that is failing to write like any other, unless the run already failed.
+        if written.is_err() && result.is_ok() {
+            result = Err(InterpreterError::FailedToWrite { pc: None });
         }
+    }
+
+    if let Some(cells) = parsed.dump_tape {
+        dump_tape(prog, cells);
+    }
+
+    if let Some(profile) = prog.profile() {
+        print_profile(profile);
+    }
+
+    if result.as_ref().is_ok_and(|stats| stats.halted) {
+        eprintln!("{}: halted at instruction {}", name, prog.pc());
+    }
 
-        self.ptr -= 1;
-        // self.ptr = self.ptr.wrapping_sub(1);
+    if let (Ok(stats), true) = (&result, parsed.quiet) {
+        eprintln!(
+            "{} steps, {} bytes of output",
+            stats.steps, stats.output_bytes
+        );
+    }
 
-        Ok(self.arr[self.ptr as usize])
+    // Enough to size `--tape-size` (and spot a runaway pointer) from.
+    match &result {
+        Ok(stats) if parsed.dump_tape.is_some() || prog.profile().is_some() || parsed.quiet => {
+            eprintln!(
+                "furthest cell: {}, biggest value: {}",
+                stats.max_ptr, stats.max_value
+            )
+        }
+        _ => (),
     }
 
-    pub fn is_empty(&self) -> bool {
-        match self.ptr {
-            0 => true,
-            _ => false,
+    if let Err(e) = result {
+        if parsed.debug_on_error
+            || matches!(
+                e,
+                InterpreterError::Breakpoint { .. } | InterpreterError::Interrupted { .. }
+            )
+        {
+            print_failure_state(prog);
         }
+
+        eprintln!("{}: {}", name, error_message(&e, parsed.color.enabled()));
+        process::exit(exit_code(&e));
     }
+}
+
+/// Appends the source in `reader` to `buffer`, returning how many bytes it had.
+///
+/// Stops one byte past `limit`, so a source that is too long is never read
+/// any further than it takes to tell.
+fn read_source(reader: impl Read, buffer: &mut String, limit: u64) -> io::Result<u64> {
+    let mut bytes = Vec::new();
 
-    pub fn is_full(&self) -> bool {
-        self.ptr == STACK_SIZE.into()
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 <= limit {
+        let source = String::from_utf8(bytes).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })?;
+
+        // Every file gets its own mark, so it has to go before they are glued together.
+        buffer.push_str(source.strip_prefix('\u{feff}').unwrap_or(&source));
+
+        return Ok(source.len() as u64);
     }
+
+    Ok(bytes.len() as u64)
 }
 
-#[derive(Clone)]
-struct Instruction {
-    pub operator: Op,
-    pub operand: u16,
+/// Passes output through while remembering the last byte written.
+struct LastByte<W> {
+    inner: W,
+    last: u8,
 }
 
-impl Default for Instruction {
-    fn default() -> Self {
-        Instruction {
-            operator: Op::End,
-            operand: 0,
+impl<W: Write> Write for LastByte<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        if let Some(&last) = buf[..written].last() {
+            self.last = last;
         }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Opens whatever `,` should read from, exiting if it is a file that won't open.
+fn open_input(input: &Option<InputSource>, prompt: bool) -> Box<dyn Read + '_> {
+    let reader: Box<dyn Read + '_> = match input {
+        Some(InputSource::File(path)) => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        Some(InputSource::Data(data)) => Box::new(data.as_bytes()),
+        // Stdin is left unlocked so the debugger can still read its commands.
+        None => Box::new(io::stdin()),
+    };
+
+    match prompt {
+        true => Box::new(Prompt { inner: reader }),
+        false => reader,
     }
 }
 
-struct Program {
-    instructions: [Instruction; PROGRAM_SIZE as usize],
-    stack: Stack,
+/// Puts a `?` on stderr every time `,` goes to read, so it's clear the program
+/// is waiting for input rather than stuck.
+struct Prompt<R> {
+    inner: R,
 }
 
-impl Program {
-    pub fn new() -> Program {
-        Program {
-            instructions: array::from_fn(|_| Instruction::default()),
-            stack: Stack::new(),
+impl<R: Read> Read for Prompt<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        eprint!("?");
+        self.inner.read(buf)
+    }
+}
+
+/// Writes every byte to stderr as well, with its code, like `A(65)`, so it's
+/// clear which value printed what.
+struct ShowCodes<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for ShowCodes<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        let mut codes = String::new();
+
+        for &byte in &buf[..written] {
+            codes.push_str(&format!("{}({}) ", byte.escape_ascii(), byte));
+
+            // Keep the codes for each line of output on a line of their own.
+            if byte == b'\n' {
+                codes.push('\n');
+            }
         }
+
+        eprint!("{}", codes);
+
+        Ok(written)
     }
 
-    pub fn compile(&mut self, fp: &String) -> Statuses {
-        let mut pc: u16 = 0;
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
 
-        for c in fp.trim().chars() {
-            if !pc < PROGRAM_SIZE {
-                break;
+/// Opens whatever `.` should write to, exiting if it is a file that can't be created.
+fn open_output(parsed: &Args) -> Box<dyn Write> {
+    if parsed.quiet {
+        return Box::new(io::sink());
+    }
+
+    let output: Box<dyn Write> = match &parsed.output_file {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
             }
+        },
+        None => Box::new(io::stdout().lock()),
+    };
 
-            let idx = pc as usize;
+    match parsed.show_codes {
+        true => Box::new(ShowCodes { inner: output }),
+        false => output,
+    }
+}
 
-            match c {
-                '>' => self.instructions[idx].operator = Op::IncDp,
-                '<' => self.instructions[idx].operator = Op::DecDp,
-                '+' => self.instructions[idx].operator = Op::IncVal,
-                '-' => self.instructions[idx].operator = Op::DecVal,
-                '.' => self.instructions[idx].operator = Op::Out,
-                ',' => self.instructions[idx].operator = Op::In,
-                '[' => {
-                    self.instructions[idx].operator = Op::JmpFwd;
+/// Reads BF a line at a time and runs it on a tape that carries over from one
+/// line to the next, until `:quit` or the end of stdin.
+fn repl(parsed: &Args) {
+    let mut prog = Program::with_config(parsed.config.clone());
+    let mut input = open_input(&parsed.input, parsed.prompt);
+    let color = parsed.color.enabled();
+    let mut pending = String::new();
 
-                    if self.stack.is_full() {
-                        return Statuses::Failure;
-                    }
+    prog.reset();
 
-                    self.stack
-                        .push(pc)
-                        .expect("Critical error, failed to push to stack");
-                }
-                ']' => {
-                    if self.stack.is_empty() {
-                        return Statuses::Failure;
-                    }
+    loop {
+        print!("{}", if pending.is_empty() { "> " } else { ". " });
+        io::stdout().flush().expect("Could not write the prompt");
 
-                    let jmp_pc: u16 = self
-                        .stack
-                        .pop()
-                        .expect("Critical error, failed to pop value off stack");
+        let mut line = String::new();
 
-                    self.instructions[idx].operator = Op::JmpBck;
-                    self.instructions[idx].operand = jmp_pc;
-                    self.instructions[jmp_pc as usize].operand = pc;
-                }
-                _ => pc = pc.wrapping_sub(1),
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => (),
+        }
+
+        match line.trim() {
+            ":quit" | ":q" => break,
+            ":reset" => {
+                pending.clear();
+                prog.reset();
+                continue;
+            }
+            ":tape" => {
+                dump_tape(&prog, parsed.dump_tape.unwrap_or(0));
+                continue;
             }
+            ":ptr" => {
+                println!("{}", prog.pointer());
+                continue;
+            }
+            _ => pending.push_str(&line),
+        }
 
-            pc = pc.wrapping_add(1);
+        // Keep reading lines until every `[` typed so far has been closed.
+        match prog.compile(&pending) {
+            Ok(()) => (),
+            Err(CompileError::UnclosedBracket { .. }) => continue,
+            Err(e) => {
+                eprintln!("{}", error_message(&e, color));
+                pending.clear();
+                continue;
+            }
         }
 
-        if !self.stack.is_empty() || pc == PROGRAM_SIZE {
-            return Statuses::Failure;
+        pending.clear();
+
+        let mut output = LastByte {
+            inner: io::stdout(),
+            last: b'\n',
+        };
+        let result = prog.resume_with(&mut input, &mut output);
+
+        // Put the next prompt on a line of its own.
+        if output.last != b'\n' {
+            println!();
         }
 
-        self.instructions[pc as usize].operator = Op::End;
+        if let Err(e) = result {
+            eprintln!("{}", error_message(&e, color));
+        }
+    }
+}
 
-        Statuses::Success
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const RESET: &str = "\x1b[0m";
+
+fn error_message(e: &impl fmt::Display, color: bool) -> String {
+    match color {
+        true => format!("{}error:{} {}", RED, RESET, e),
+        false => format!("error: {}", e),
     }
+}
 
-    pub fn execute(&mut self) -> Statuses {
-        let mut data: [u16; DATA_SIZE as usize] = [0; DATA_SIZE as usize];
-        let mut pc: u16 = 0;
-        let mut ptr: u32 = 0;
+/// Most characters of a line [`snippet`] shows, the rest is cut off around the column.
+const SNIPPET_WIDTH: usize = 80;
 
-        while (self.instructions[pc as usize].operator != Op::End) && (ptr < DATA_SIZE.into()) {
-            match self.instructions[pc as usize].operator {
-                // Op::OpIncDp => ptr += 1,
-                Op::IncDp => ptr = ptr.wrapping_add(1),
-                // Op::OpDecDp => ptr -= 1,
-                Op::DecDp => ptr = ptr.wrapping_sub(1),
-                // Op::OpIncVal => data[ptr as usize] += 1,
-                Op::IncVal => data[ptr as usize] = data[ptr as usize].wrapping_add(1),
-                // Op::OpDecVal => data[ptr as usize] -= 1,
-                Op::DecVal => data[ptr as usize] = data[ptr as usize].wrapping_sub(1),
-                Op::Out => print!(
-                    "{}",
-                    char::from_u32(data[ptr as usize].into())
-                        .expect("failed to convert data to char")
-                ),
-                Op::In => {
-                    data[ptr as usize] = {
-                        let mut buffer = [0u8; 2];
-                        match io::stdin().read_exact(&mut buffer) {
-                            Ok(_) => u16::from_be_bytes(buffer).into(),
-                            Err(_) => panic!("Failed to convert input to u16 char"),
-                        }
-                    }
-                }
-                Op::JmpFwd => {
-                    if data[ptr as usize] == 0 {
-                        pc = self.instructions[pc as usize].operand
-                    }
-                }
-                Op::JmpBck => {
-                    if data[ptr as usize] != 0 {
-                        pc = self.instructions[pc as usize].operand
-                    }
-                }
-                _ => return Statuses::Failure,
+/// Shows the source line an error is on with a caret under `column`, like
+///
+/// ```text
+///   3 | ++]
+///     |   ^
+/// ```
+///
+/// A line longer than [`SNIPPET_WIDTH`], like a whole minified program, only
+/// shows the stretch around the column with `...` where it was cut.
+fn snippet(text: &str, line: usize, column: usize, color: bool) -> String {
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    let chars = text.chars().collect::<Vec<_>>();
+    let start = (column - 1)
+        .saturating_sub(SNIPPET_WIDTH / 2)
+        .min(chars.len().saturating_sub(SNIPPET_WIDTH));
+    let end = (start + SNIPPET_WIDTH).min(chars.len());
+    let text = format!(
+        "{}{}{}",
+        if start > 0 { "..." } else { "" },
+        chars[start..end].iter().collect::<String>(),
+        if end < chars.len() { "..." } else { "" }
+    );
+    // Keep tabs so the caret lines up however wide the terminal draws them.
+    let indent = text
+        .chars()
+        .take(column - 1 - start + if start > 0 { 3 } else { 0 })
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    let (blue, red, reset) = match color {
+        true => (BLUE, RED, RESET),
+        false => ("", "", ""),
+    };
+
+    format!(
+        "{blue}  {gutter} |{reset}\n{blue}  {number} |{reset} {text}\n{blue}  {gutter} |{reset} {indent}{red}^{reset}\n"
+    )
+}
+
+/// Prints the compiled program as a listing, one instruction per line with its
+/// index, the jump target for brackets and the count for everything that repeats.
+fn disassemble(prog: &Program, explain: bool) {
+    let instructions = prog.instructions();
+    let config = prog.config();
+
+    for (pc, instruction) in instructions.iter().enumerate() {
+        let name = format!("{:?}", instruction.operator);
+        let n = instruction.operand;
+        // The operand is only masked to the cell when it runs, so show what it sets.
+        let value = config.cell_value(n as u32 & config.cell_width.mask());
+
+        let line = match instruction.operator {
+            Op::JmpFwd | Op::JmpBck => format!("{:04}  {:<9} -> {:04}", pc, name, n),
+            Op::IncDp | Op::DecDp | Op::IncVal | Op::DecVal | Op::ScanRight | Op::ScanLeft => {
+                format!("{:04}  {:<9} {}", pc, name, n)
             }
-            // pc += 1;
-            pc = pc.wrapping_add(1);
+            Op::SetConst => format!("{:04}  {:<9} {}", pc, name, value),
+            _ => format!("{:04}  {}", pc, name),
+        };
+
+        match explain {
+            true => println!("{:<24}; {}", line, explanation(instruction, value)),
+            false => println!("{}", line),
         }
+    }
+
+    let end = format!("{:04}  {:?}", instructions.len(), Op::End);
+
+    match explain {
+        true => println!("{:<24}; {}", end, explanation(&Instruction::default(), 0)),
+        false => println!("{}", end),
+    }
+}
+
+/// What an instruction does, in words, for `--explain`. `value` is what a
+/// SetConst sets the cell to.
+fn explanation(instruction: &Instruction, value: i64) -> String {
+    let n = instruction.operand;
+    let cells = match n {
+        1 => "1 cell".to_owned(),
+        _ => format!("{} cells", n),
+    };
+
+    match instruction.operator {
+        Op::End => "the end of the program".to_owned(),
+        Op::IncDp => format!("move the pointer right {}", cells),
+        Op::DecDp => format!("move the pointer left {}", cells),
+        Op::IncVal => format!("add {} to the current cell", n),
+        Op::DecVal => format!("subtract {} from the current cell", n),
+        Op::Out => "write the current cell to the output".to_owned(),
+        Op::In => "read the next input into the current cell".to_owned(),
+        Op::JmpFwd => format!("if the cell is zero, jump past the matching ] at {:04}", n),
+        Op::JmpBck => format!(
+            "if the cell is not zero, jump back to the matching [ at {:04}",
+            n
+        ),
+        Op::Clear => "set the current cell to 0".to_owned(),
+        Op::ScanRight => format!("move right {} at a time until the cell is zero", cells),
+        Op::ScanLeft => format!("move left {} at a time until the cell is zero", cells),
+        Op::Dump => "print the cells around the pointer to stderr".to_owned(),
+        Op::Halt => "stop the program here".to_owned(),
+        Op::SetConst => format!("set the current cell to {}", value),
+    }
+}
+
+/// Prints how many times each instruction appears in `source`, most common
+/// first.
+fn print_histogram(source: &str, debug_ops: bool) {
+    let mut counts = [0usize; Op::ALL.len()];
 
-        match ptr != DATA_SIZE.into() {
-            true => Statuses::Success,
-            false => Statuses::Failure,
+    for (_, op) in mindsuck::tokens(source) {
+        if debug_ops || !matches!(op, Op::Dump | Op::Halt) {
+            counts[op as usize] += 1;
         }
     }
+
+    let total = counts.iter().sum::<usize>();
+    let mut ops = Op::ALL
+        .into_iter()
+        .filter(|&op| counts[op as usize] != 0)
+        .collect::<Vec<_>>();
+
+    // Stable, so ties stay in the order of `Op::ALL`.
+    ops.sort_by_key(|&op| std::cmp::Reverse(counts[op as usize]));
+
+    for op in ops {
+        let count = counts[op as usize];
+
+        println!(
+            "{:<8} {:>8} {:>6.1}%",
+            format!("{:?}", op),
+            count,
+            count as f64 * 100.0 / total as f64
+        );
+    }
+
+    println!("{:<8} {:>8}", "total", total);
 }
 
-#[derive(Debug)]
-enum Error {
-    FailedToExecute,
-    FailedToCompile,
+/// Says how much `--optimize` did to the program compiled from `source`,
+/// going by what it compiled to.
+fn print_optimizations(prog: &Program, source: &str) {
+    let debug_ops = prog.config().debug_ops;
+    let before = mindsuck::tokens(source)
+        .filter(|(_, op)| debug_ops || !matches!(op, Op::Dump | Op::Halt))
+        .count();
+    let instructions = prog.instructions();
+    let count = |matches: fn(&Instruction) -> bool| {
+        instructions
+            .iter()
+            .filter(|instruction| matches(instruction))
+            .count()
+    };
+    // A scan over a run, like `[>>]`, squashed the run first.
+    let runs = count(|instruction| {
+        matches!(
+            instruction.operator,
+            Op::IncDp | Op::DecDp | Op::IncVal | Op::DecVal | Op::ScanRight | Op::ScanLeft
+        ) && instruction.operand > 1
+    });
+    // Every set started out as a clear.
+    let clears = count(|instruction| matches!(instruction.operator, Op::Clear | Op::SetConst));
+    let scans = count(|instruction| matches!(instruction.operator, Op::ScanRight | Op::ScanLeft));
+    let sets = count(|instruction| instruction.operator == Op::SetConst);
+
+    eprintln!(
+        "coalesced {} runs, replaced {} clear loops and {} scan loops, fused {} set-consts, reduced {} instructions to {}",
+        runs,
+        clears,
+        scans,
+        sets,
+        before,
+        instructions.len()
+    );
 }
 
-fn main() -> Result<(), Error> {
-    let args = std::env::args().collect::<Vec<String>>();
+/// Runs the program `--repeat` times from a fresh tape, with the same input
+/// every time, printing how long each run took. Only the first run's output
+/// is written, and a later run that writes anything different is an error.
+///
+/// Every run is the same, so the tape dump and profile are the last run's.
+fn run_repeatedly(prog: &mut Program, parsed: &Args, name: &str) {
+    let color = parsed.color.enabled();
+    let mut bytes = Vec::new();
+    let mut first = None;
+    let mut total = Duration::ZERO;
 
-    if args.len() != 2
-        || !Path::new(
-            args.get(1)
-                .expect("The compiler could not find a file argument"),
-        )
-        .exists()
+    open_input(&parsed.input, false)
+        .read_to_end(&mut bytes)
+        .expect("Could not read the input");
+
+    for run in 1..=parsed.repeat {
+        let mut output = Vec::new();
+        let start = Instant::now();
+        let result = prog.execute_with(&mut &bytes[..], &mut output);
+        let elapsed = start.elapsed();
+
+        total += elapsed;
+
+        match result {
+            Ok(stats) => eprintln!("run {}: {} steps in {:.2?}", run, stats.steps, elapsed),
+            Err(e) => {
+                if parsed.debug_on_error
+                    || matches!(
+                        e,
+                        InterpreterError::Breakpoint { .. } | InterpreterError::Interrupted { .. }
+                    )
+                {
+                    print_failure_state(prog);
+                }
+
+                eprintln!("{}: run {}: {}", name, run, error_message(&e, color));
+                process::exit(exit_code(&e));
+            }
+        }
+
+        match &first {
+            None => first = Some(output),
+            Some(first) if *first != output => {
+                eprintln!(
+                    "{}: {}",
+                    name,
+                    error_message(
+                        &format!("run {} wrote something different to run 1", run),
+                        color
+                    )
+                );
+                process::exit(EXIT_RUNTIME);
+            }
+            Some(_) => (),
+        }
+    }
+
+    eprintln!("average: {:.2?}", total / parsed.repeat);
+
+    let mut output = open_output(parsed);
+    let mut first = first.unwrap_or_default();
+
+    if parsed.final_newline && first.last().is_some_and(|&last| last != b'\n') {
+        first.push(b'\n');
+    }
+
+    if output
+        .write_all(&first)
+        .and_then(|_| output.flush())
+        .is_err()
     {
-        eprintln!("Usage: {} filename\n", args[0]);
+        let e = InterpreterError::FailedToWrite { pc: None };
+
+        eprintln!("{}: {}", name, error_message(&e, color));
+        process::exit(exit_code(&e));
     }
 
-    let mut buffer = String::new();
-    let mut file = File::open(&args[1]).expect("Could not open the file given");
-    file.read_to_string(&mut buffer)
-        .expect("Coud not read the file given");
+    if let Some(cells) = parsed.dump_tape {
+        dump_tape(prog, cells);
+    }
 
-    let mut prog = Program::new();
+    if let Some(profile) = prog.profile() {
+        print_profile(profile);
+    }
+}
 
-    match prog.compile(&buffer) {
-        Statuses::Success => match prog.execute() {
-            Statuses::Success => Ok(()),
-            Statuses::Failure => Err(Error::FailedToExecute),
-        },
-        Statuses::Failure => Err(Error::FailedToCompile),
+/// Runs the program once with every combination of optimization passes, its
+/// output thrown away, and prints how many steps each took and how long.
+fn bench(config: &Config, source: &str, input: &Option<InputSource>) {
+    catch_interrupts();
+
+    let mut bytes = Vec::new();
+
+    // Every run gets the same input, so it has to be read up front.
+    open_input(input, false)
+        .read_to_end(&mut bytes)
+        .expect("Could not read the input");
+
+    println!("{:<20} {:>14} {:>12}", "passes", "steps", "time");
+
+    for bits in 0..16 {
+        let passes = Passes {
+            coalesce: bits & 1 != 0,
+            clear: bits & 2 != 0,
+            scan: bits & 4 != 0,
+            set: bits & 8 != 0,
+        };
+        let name = [
+            ("coalesce", passes.coalesce),
+            ("clear", passes.clear),
+            ("scan", passes.scan),
+            ("set", passes.set),
+        ]
+        .iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join("+");
+        let name = if name.is_empty() { "none" } else { &name };
+        let mut prog = Program::with_config(Config {
+            optimize: passes,
+            ..config.clone()
+        });
+
+        prog.compile(source)
+            .expect("Optimizing doesn't change whether a program compiles");
+
+        let start = Instant::now();
+        let result = prog.execute_with(&mut &bytes[..], &mut io::sink());
+        let elapsed = start.elapsed();
+
+        match result {
+            Ok(stats) => println!("{:<20} {:>14} {:>12.2?}", name, stats.steps, elapsed),
+            // The rest would only be interrupted straight away too.
+            Err(e @ InterpreterError::Interrupted { .. }) => {
+                println!("{:<20} error: {}", name, e);
+                process::exit(EXIT_INTERRUPTED);
+            }
+            Err(e) => println!("{:<20} error: {}", name, e),
+        }
+    }
+}
+
+/// Shows the instruction a run stopped at and the cells around the pointer,
+/// the same way the debugger does.
+fn print_failure_state(prog: &Program) {
+    let pc = prog.pc();
+    let (operator, operand) = match prog.instructions().get(pc) {
+        Some(instruction) => (instruction.operator, instruction.operand),
+        None => (Op::End, 0),
+    };
+
+    eprintln!(
+        "pc {:04} {:?} {} | ptr {} = {}",
+        pc,
+        operator,
+        operand,
+        prog.pointer(),
+        prog.config().cell_value(prog.tape()[prog.pointer()])
+    );
+    eprintln!("{}", prog.tape_window(8));
+}
+
+/// Prints the first `cells` cells of the tape, or when `cells` is 0 every one
+/// that doesn't hold the `--init-cell` value any more.
+fn dump_tape(prog: &Program, cells: usize) {
+    eprintln!("ptr: {}", prog.pointer());
+
+    let shown = match cells {
+        0 => prog.tape().changed(prog.config().fill()),
+        _ => prog.tape().iter().take(cells).enumerate().collect(),
+    };
+
+    for (offset, value) in shown {
+        eprintln!("{:05}: {}", offset, prog.config().cell_value(value));
+    }
+}
+
+fn print_profile(profile: &Profile) {
+    eprintln!(
+        "executed {} instructions in {:.2?}",
+        profile.total(),
+        profile.elapsed
+    );
+
+    for op in Op::ALL {
+        if profile.count(op) != 0 {
+            eprintln!("  {:<8} {}", format!("{:?}", op), profile.count(op));
+        }
+    }
+
+    for (pc, entries) in profile.loop_entries.iter().enumerate() {
+        if *entries != 0 {
+            eprintln!("  loop at {:04} entered {} times", pc, entries);
+        }
     }
 }
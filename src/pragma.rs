@@ -0,0 +1,70 @@
+use crate::{CellWidth, Config, EofMode, PointerPolicy};
+
+impl Config {
+    /// Applies the `!name value` pragma lines at the top of `source`, like
+    /// `!cell 8` or `!tape 30000`, on top of whatever is already set.
+    ///
+    /// Returns the source with the pragma lines blanked out, so nothing in them
+    /// gets compiled while line numbers stay put, and a warning for every pragma
+    /// that was not understood.
+    pub fn apply_pragmas(&mut self, source: &str) -> (String, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut rest = source;
+        let mut blanked = String::new();
+
+        while let Some(line) = rest.split_inclusive('\n').next() {
+            let pragma = match line.trim() {
+                "" => None,
+                text if text.starts_with('!') => Some(&text[1..]),
+                _ => break,
+            };
+
+            if let Some(pragma) = pragma {
+                if let Err(warning) = self.apply_pragma(pragma) {
+                    warnings.push(warning);
+                }
+            }
+
+            if line.ends_with('\n') {
+                blanked.push('\n');
+            }
+
+            rest = &rest[line.len()..];
+        }
+
+        blanked.push_str(rest);
+
+        (blanked, warnings)
+    }
+
+    fn apply_pragma(&mut self, pragma: &str) -> Result<(), String> {
+        let (name, value) = pragma
+            .split_once(char::is_whitespace)
+            .map(|(name, value)| (name, value.trim()))
+            .unwrap_or((pragma, ""));
+        let invalid = || format!("ignoring invalid value '{}' for pragma !{}", value, name);
+
+        match name {
+            "cell" => {
+                self.cell_width = value
+                    .parse()
+                    .ok()
+                    .and_then(CellWidth::from_bits)
+                    .ok_or_else(invalid)?
+            }
+            "tape" => self.tape_size = value.parse().map_err(|_| invalid())?,
+            "maxtape" => {
+                self.max_tape = value
+                    .parse()
+                    .ok()
+                    .filter(|&cells| cells > 0)
+                    .ok_or_else(invalid)?
+            }
+            "eof" => self.eof = EofMode::from_name(value).ok_or_else(invalid)?,
+            "pointer" => self.pointer = PointerPolicy::from_name(value).ok_or_else(invalid)?,
+            _ => return Err(format!("ignoring unknown pragma !{}", pragma)),
+        }
+
+        Ok(())
+    }
+}
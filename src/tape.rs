@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+/// Cells in each page of a sparse [`Tape`].
+const PAGE: usize = 4096;
+
+/// The cells a [`Program`](crate::Program) runs on.
+///
+/// A dense tape is one big zeroed `Vec`, which is the quickest to run on. A
+/// sparse one, see [`Config::sparse`](crate::Config::sparse), only allocates
+/// pages of cells the first time something is written to them, so a tape of
+/// millions of cells costs nothing beyond the few a program actually touches.
+/// Every cell reads as the fill, 0 unless made with [`Tape::filled`], until
+/// then either way.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tape {
+    Dense(Vec<u32>),
+    Sparse {
+        pages: HashMap<usize, Box<[u32; PAGE]>>,
+        len: usize,
+        fill: u32,
+    },
+}
+
+impl Tape {
+    /// A tape of `len` zeroed cells, sparse or not.
+    pub fn new(len: usize, sparse: bool) -> Tape {
+        Tape::filled(len, sparse, 0)
+    }
+
+    /// A tape of `len` cells that all start out as `fill`.
+    pub fn filled(len: usize, sparse: bool, fill: u32) -> Tape {
+        match sparse {
+            true => Tape::Sparse {
+                pages: HashMap::new(),
+                len,
+                fill,
+            },
+            false => Tape::Dense(vec![fill; len]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Tape::Dense(cells) => cells.len(),
+            Tape::Sparse { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cell at `i`, or `None` past the end of the tape.
+    pub fn get(&self, i: usize) -> Option<u32> {
+        (i < self.len()).then(|| self[i])
+    }
+
+    /// The cell at `i` to change, or `None` past the end of the tape.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut u32> {
+        match i < self.len() {
+            true => Some(&mut self[i]),
+            false => None,
+        }
+    }
+
+    /// Every cell from the first to the last, untouched ones included.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len()).map(|i| self[i])
+    }
+
+    /// The index and value of every non-zero cell, in order, without going
+    /// through the untouched pages of a sparse tape unless they are filled
+    /// with something other than 0.
+    pub fn non_zero(&self) -> Vec<(usize, u32)> {
+        self.changed(0)
+    }
+
+    /// The index and value of every cell that doesn't hold `fill`, in order,
+    /// without going through the untouched pages of a sparse tape unless they
+    /// are filled with something else.
+    pub fn changed(&self, fill: u32) -> Vec<(usize, u32)> {
+        match self {
+            Tape::Dense(cells) => cells
+                .iter()
+                .enumerate()
+                .filter(|&(_, &cell)| cell != fill)
+                .map(|(i, &cell)| (i, cell))
+                .collect(),
+            Tape::Sparse {
+                pages,
+                fill: untouched,
+                ..
+            } if *untouched == fill => {
+                let mut numbers = pages.keys().copied().collect::<Vec<_>>();
+
+                numbers.sort_unstable();
+                numbers
+                    .into_iter()
+                    .flat_map(|number| {
+                        pages[&number]
+                            .iter()
+                            .enumerate()
+                            .filter(|&(_, &cell)| cell != fill)
+                            .map(move |(i, &cell)| (number * PAGE + i, cell))
+                    })
+                    .collect()
+            }
+            Tape::Sparse { .. } => self
+                .iter()
+                .enumerate()
+                .filter(|&(_, cell)| cell != fill)
+                .collect(),
+        }
+    }
+
+    /// Grows or shrinks the tape to `new_len` cells, new ones are `fill`.
+    pub(crate) fn resize(&mut self, new_len: usize, fill: u32) {
+        match self {
+            Tape::Dense(cells) => cells.resize(new_len, fill),
+            Tape::Sparse { pages, len, .. } => {
+                pages.retain(|&number, _| number * PAGE < new_len);
+
+                if let Some(page) = pages.get_mut(&(new_len / PAGE)) {
+                    page[new_len % PAGE..].fill(fill);
+                }
+
+                *len = new_len;
+            }
+        }
+    }
+
+    /// Where the first zero cell at or after `from` is, or the length of the
+    /// tape if there isn't one.
+    pub(crate) fn next_zero(&self, from: usize) -> usize {
+        match self {
+            Tape::Dense(cells) => cells[from..]
+                .iter()
+                .position(|&cell| cell == 0)
+                .map_or(cells.len(), |zero| from + zero),
+            Tape::Sparse { .. } => (from..self.len())
+                .find(|&i| self[i] == 0)
+                .unwrap_or(self.len()),
+        }
+    }
+
+    /// Where the last zero cell before `to` is, if there is one.
+    pub(crate) fn previous_zero(&self, to: usize) -> Option<usize> {
+        match self {
+            Tape::Dense(cells) => cells[..to].iter().rposition(|&cell| cell == 0),
+            Tape::Sparse { .. } => (0..to).rev().find(|&i| self[i] == 0),
+        }
+    }
+}
+
+impl Index<usize> for Tape {
+    type Output = u32;
+
+    fn index(&self, i: usize) -> &u32 {
+        match self {
+            Tape::Dense(cells) => &cells[i],
+            Tape::Sparse { pages, len, fill } => {
+                assert!(i < *len, "cell {} is off the end of the tape", i);
+
+                pages.get(&(i / PAGE)).map_or(fill, |page| &page[i % PAGE])
+            }
+        }
+    }
+}
+
+impl IndexMut<usize> for Tape {
+    fn index_mut(&mut self, i: usize) -> &mut u32 {
+        match self {
+            Tape::Dense(cells) => &mut cells[i],
+            Tape::Sparse { pages, len, fill } => {
+                assert!(i < *len, "cell {} is off the end of the tape", i);
+
+                &mut pages
+                    .entry(i / PAGE)
+                    .or_insert_with(|| Box::new([*fill; PAGE]))[i % PAGE]
+            }
+        }
+    }
+}
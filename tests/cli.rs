@@ -0,0 +1,754 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn mindsuck(args: &[&str], source: &str, input: &[u8]) -> Vec<u8> {
+    let path = std::env::temp_dir().join(format!(
+        "mindsuck-{}-{:x}.bf",
+        std::process::id(),
+        source
+            .bytes()
+            .fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b.into()))
+    ));
+    std::fs::write(&path, source).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(args)
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // A program that never reads stdin can be done before this gets written.
+    match child.stdin.take().unwrap().write_all(input) {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => (),
+        result => result.unwrap(),
+    }
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    output.stdout
+}
+
+#[test]
+fn clear_loops_match_the_naive_interpreter() {
+    let source = "++++++++[>++++++++<-]>+.[-]+++++[>++++++++++<-]>.[+]++++.[-]>[-]<<[-]+++.[-]";
+    let naive = mindsuck(&[], source, b"");
+
+    assert_eq!(naive, [65, 50, 4, 3]);
+    assert_eq!(mindsuck(&["--optimize"], source, b""), naive);
+}
+
+#[test]
+fn scan_loops_match_the_naive_interpreter() {
+    // Marks a few cells, then scans across them both ways, a cell and two at a time.
+    let source =
+        ">+>+>+>+>>+<<<<<[>]+++++++++++++++++++++++++++++++++.<[<]>.>>>>>>+>>+>>+<<<<[>>]>+[<<]++.>>.";
+    let naive = mindsuck(&[], source, b"");
+
+    assert_eq!(naive, [33, 1, 2, 1]);
+    assert_eq!(mindsuck(&["--optimize"], source, b""), naive);
+
+    // Scans off the ends of the tape still grow or wrap it like single moves.
+    for (args, source) in [
+        (["--tape-size", "2"], "+>+[>]+."),
+        (["--pointer", "wrap"], "+[<]+."),
+    ] {
+        let naive = mindsuck(&args, source, b"");
+
+        assert_eq!(naive, [1]);
+        assert_eq!(
+            mindsuck(&[&args[..], &["--optimize"]].concat(), source, b""),
+            naive
+        );
+    }
+}
+
+#[test]
+fn scans_that_never_find_a_zero_still_stop_at_the_limits() {
+    let source = std::env::temp_dir().join(format!("mindsuck-{}-scan.bf", std::process::id()));
+
+    std::fs::write(&source, "+>+>+[>]").unwrap();
+
+    for pointer in [
+        &["--wrap-tape", "--tape-size", "3"][..],
+        &["--pointer", "clamp", "--max-tape", "3"],
+    ] {
+        for limit in [["--max-steps", "1000"], ["--timeout-ms", "100"]] {
+            for optimize in [&[][..], &["--optimize"]] {
+                let status = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+                    .args(pointer)
+                    .args(limit)
+                    .args(optimize)
+                    .arg(&source)
+                    .stderr(Stdio::null())
+                    .status()
+                    .unwrap();
+
+                assert_eq!(
+                    status.code(),
+                    Some(4),
+                    "{:?} {:?} {:?}",
+                    pointer,
+                    limit,
+                    optimize
+                );
+            }
+        }
+    }
+
+    std::fs::remove_file(&source).unwrap();
+}
+
+#[test]
+fn set_constants_match_the_naive_interpreter() {
+    // Sets cells over whatever was there, up and down and past zero.
+    let source = "+++[-]+++++++++.>++++[+]---.<[-]+-+.[-]-[-]-.";
+
+    for width in ["8", "16"] {
+        let naive = mindsuck(&["--cell-width", width], source, b"");
+
+        // Only the low byte gets printed, wider cells or not.
+        assert_eq!(naive, [9, 253, 1, 255]);
+
+        for dispatch in ["table", "match"] {
+            assert_eq!(
+                mindsuck(
+                    &["--cell-width", width, "--dispatch", dispatch, "--optimize"],
+                    source,
+                    b""
+                ),
+                naive
+            );
+        }
+    }
+}
+
+#[test]
+fn deeply_nested_loops_jump_to_their_matching_bracket() {
+    // Ten nested loops that each run twice around a single print.
+    let source = format!(
+        "{}{}{}",
+        ">++[".repeat(10),
+        "<<<<<<<<<<.>>>>>>>>>>",
+        "-]<".repeat(10)
+    );
+    let expected = vec![0; 1 << 10];
+
+    assert_eq!(mindsuck(&[], &source, b""), expected);
+    assert_eq!(mindsuck(&["--optimize"], &source, b""), expected);
+}
+
+#[test]
+fn cat_echoes_raw_bytes_until_eof() {
+    // Zero would end the loop early, every other byte value has to come back untouched.
+    let input: Vec<u8> = (1..=255).chain([b'\n', 0x80, 0xff]).collect();
+
+    assert_eq!(mindsuck(&[], ",[.,]", &input), input);
+    assert_eq!(mindsuck(&["--eof", "unchanged"], ",[.[-],]", &input), input);
+}
+
+#[test]
+fn final_newline_only_adds_a_missing_one() {
+    assert_eq!(
+        mindsuck(&["--final-newline"], "+++++[>+++++++++++++<-]>.", b""),
+        b"A\n"
+    );
+    assert_eq!(mindsuck(&["--final-newline"], "++++++++++.", b""), b"\n");
+    assert_eq!(mindsuck(&["--final-newline"], "+", b""), b"");
+}
+
+#[test]
+fn input_can_be_given_inline() {
+    assert_eq!(mindsuck(&["--input", "hi"], ",[.,]", b"ignored"), b"hi");
+    assert_eq!(
+        mindsuck(&["--input", "a", "--eof", "minusone"], ",.,.", b""),
+        b"a\xff"
+    );
+}
+
+#[test]
+fn output_can_go_to_a_file() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}.out", std::process::id()));
+    let stdout = mindsuck(
+        &["--output-file", path.to_str().unwrap(), "--final-newline"],
+        ",[.,]",
+        b"to the file",
+    );
+
+    assert_eq!(stdout, b"");
+    assert_eq!(std::fs::read(&path).unwrap(), b"to the file\n");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn show_codes_annotates_the_output_on_stderr() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-codes.bf", std::process::id()));
+    std::fs::write(&path, "++++++++[>+++++++++<-]>.+.[-]++++++++++.").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .arg("--show-codes")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.stdout, b"HI\n");
+    assert_eq!(output.stderr, b"H(72) I(73) \\n(10) \n");
+}
+
+#[test]
+fn bench_runs_every_combination_of_passes() {
+    let stdout = mindsuck(&["--bench"], "++++[-]>>>[<]", b"");
+    let rows = String::from_utf8(stdout).unwrap();
+    let steps = rows
+        .lines()
+        .skip(1)
+        .map(|row| {
+            let columns = row.split_whitespace().collect::<Vec<_>>();
+
+            (columns[0].to_string(), columns[1].parse::<u64>().unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(steps.len(), 16);
+    assert_eq!(steps[0], ("none".to_string(), 17));
+    assert_eq!(steps[7], ("coalesce+clear+scan".to_string(), 4));
+    assert_eq!(steps[15], ("coalesce+clear+scan+set".to_string(), 4));
+}
+
+#[test]
+fn prompt_marks_every_read_on_stderr() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--prompt", "--input", "ab", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b",.,.")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+
+    assert_eq!(output.stdout, b"ab");
+    assert_eq!(output.stderr, b"??");
+}
+
+#[test]
+fn wrap_tape_goes_round_the_tape_size() {
+    let source = "+<+<+<+.>.>.>.";
+
+    assert_eq!(
+        mindsuck(&["--wrap-tape", "--tape-size", "3"], source, b""),
+        [2, 1, 1, 2]
+    );
+    assert_eq!(mindsuck(&[], source, b""), b"");
+}
+
+#[test]
+fn compiled_programs_run_without_the_source() {
+    let bfc = std::env::temp_dir().join(format!("mindsuck-{}.bfc", std::process::id()));
+    let bfc = bfc.to_str().unwrap();
+
+    assert_eq!(
+        mindsuck(&["--optimize", "--compile-to", bfc], ",[.,]", b"not run"),
+        b""
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--run-compiled", bfc, "--input", "cat"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(bfc).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"cat");
+}
+
+#[test]
+fn repeat_runs_from_a_fresh_tape_each_time() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--repeat", "3", "--input", "ab", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b",+.,.")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"bb");
+    assert_eq!(
+        stderr
+            .lines()
+            .filter(|line| line.starts_with("run ") && line.contains(": 5 steps in "))
+            .count(),
+        3
+    );
+    assert!(stderr.lines().last().unwrap().starts_with("average: "));
+}
+
+#[test]
+fn repeat_still_adds_the_newline_and_dumps_the_tape() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--repeat", "2", "--final-newline", "--dump-tape", "2", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child.stdin.take().unwrap().write_all(b"+++.>+")?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, [3, b'\n']);
+    assert!(stderr.contains("ptr: 1\n"), "{}", stderr);
+}
+
+#[test]
+fn quiet_keeps_the_stats_but_not_the_output() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-quiet.bf", std::process::id()));
+    std::fs::write(&path, "++++++++[>++++++++<-]>+.+.+.").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .arg("--quiet")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "112 steps, 3 bytes of output\nfurthest cell: 1, biggest value: 67\n"
+    );
+}
+
+#[test]
+fn rot13_round_trips_through_the_binary() {
+    let source = include_str!("../examples/rot13.bf");
+    let text = b"The Quick Brown Fox Jumps Over The Lazy Dog, 42 times!\n";
+    let rot13 = mindsuck(&["--eof", "unchanged"], source, text);
+
+    assert_eq!(
+        rot13,
+        b"Gur Dhvpx Oebja Sbk Whzcf Bire Gur Ynml Qbt, 42 gvzrf!\n"
+    );
+    assert_eq!(mindsuck(&["--eof", "minusone"], source, &rot13), text);
+}
+
+#[test]
+fn stdout_gets_nul_and_control_bytes_untouched() {
+    let source = format!(
+        ".{}.{}.{}.",
+        "+".repeat(10),
+        "+".repeat(17),
+        "+".repeat(228)
+    );
+
+    assert_eq!(mindsuck(&[], &source, b""), [0, 10, 27, 255]);
+}
+
+#[test]
+fn programs_over_max_program_bytes_are_rejected() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-big.bf", std::process::id()));
+    std::fs::write(&path, "+".repeat(100) + ".").unwrap();
+
+    let run = |limit: &str| {
+        Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .args(["--max-program-bytes", limit])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let fits = run("101");
+    let too_big = run("100");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(fits.stdout, [100]);
+    assert_eq!(too_big.status.code(), Some(4));
+    assert!(String::from_utf8(too_big.stderr)
+        .unwrap()
+        .ends_with("program is longer than 100 bytes\n"));
+}
+
+#[test]
+fn windows_line_endings_and_boms_still_run() {
+    // The pragma is only spotted once the mark in front of it is gone.
+    let source = "\u{feff}!cell 16\r\n".to_string() + &"+".repeat(300) + "[-.]\r\n";
+
+    assert_eq!(mindsuck(&["--strict"], &source, b"").len(), 300);
+}
+
+#[test]
+fn runtime_errors_are_reported_and_fail() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-loop.bf", std::process::id()));
+    std::fs::write(&path, "+[]").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--max-steps", "100"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .ends_with("loop.bf: error: ran past the step limit\n"));
+}
+
+#[test]
+fn debug_on_error_shows_where_the_run_stopped() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-edge.bf", std::process::id()));
+    std::fs::write(&path, "++>+++<<").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .arg("--debug-on-error")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut lines = stderr.lines();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(lines.next(), Some("pc 0007 DecDp 1 | ptr 0 = 2"));
+    assert!(lines.next().unwrap().starts_with("0:[2] 1:3 2:0"));
+    assert!(lines
+        .next()
+        .unwrap()
+        .ends_with("error: instruction 7 moved the pointer off the tape from cell 0"));
+}
+
+#[test]
+fn break_exit_shows_where_the_breakpoint_hit() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-break.bf", std::process::id()));
+    std::fs::write(&path, "++[>+++<-]>.").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .args(["--break-when", "cell1>=5", "--break-exit"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut lines = stderr.lines();
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(output.stdout.is_empty());
+    assert_eq!(lines.next(), Some("pc 0006 IncVal 1 | ptr 1 = 5"));
+    assert!(lines.next().unwrap().starts_with("0:1 1:[5] 2:0"));
+    assert!(lines
+        .next()
+        .unwrap()
+        .ends_with("error: stopped at the breakpoint at instruction 6"));
+}
+
+#[test]
+fn dump_every_samples_the_tape() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-dump.bf", std::process::id()));
+    std::fs::write(&path, "+++++").unwrap();
+
+    let dumps = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .args(["--dump-every", "2"])
+            .args(extra)
+            .arg(&path)
+            .output()
+            .unwrap();
+
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .lines()
+            .filter(|line| line.contains(" | "))
+            .map(|line| line.split(" | ").next().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(dumps(&[]), ["2 pc 0002", "4 pc 0004"]);
+    assert_eq!(dumps(&["--max-steps", "3"]), ["2 pc 0002"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_compiles_without_running() {
+    let check = |source: &str| {
+        let path = std::env::temp_dir().join(format!(
+            "mindsuck-{}-check-{}.bf",
+            std::process::id(),
+            source.len()
+        ));
+        std::fs::write(&path, source).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .arg("--check")
+            .arg(&path)
+            .output()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        output
+    };
+
+    // Never finishes if it runs.
+    let fine = check("+[.]");
+    assert_eq!(fine.status.code(), Some(0));
+    assert!(fine.stdout.is_empty());
+    assert!(String::from_utf8(fine.stderr).unwrap().ends_with(": ok\n"));
+
+    let broken = check("+[.]]");
+    assert_eq!(broken.status.code(), Some(2));
+    assert!(String::from_utf8(broken.stderr)
+        .unwrap()
+        .contains(":1:5: error: unmatched ']'"));
+}
+
+#[test]
+fn errors_on_long_lines_only_show_the_part_around_them() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-long.bf", std::process::id()));
+    std::fs::write(&path, format!("{}]{}", "+".repeat(3000), "-".repeat(3000))).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines = stderr.lines().collect::<Vec<_>>();
+
+    assert!(lines[0].ends_with(":1:3001: error: unmatched ']'"));
+    assert_eq!(
+        lines[2],
+        format!("  1 | ...{}]{}...", "+".repeat(40), "-".repeat(39))
+    );
+    assert_eq!(lines[3], format!("    | {}^", " ".repeat(43)));
+}
+
+#[test]
+fn missing_files_print_usage_and_fail() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+        .arg("no-such-program.bf")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.starts_with("no-such-program.bf: no such file\nUsage: "));
+}
+
+#[test]
+fn self_input_reads_the_input_after_the_bang() {
+    // Copies its input through to the end, `[` and all.
+    let source = ",[.,]!ab[c]";
+
+    assert_eq!(mindsuck(&["--self-input"], source, b"xyz"), b"ab[c]");
+    // Without it the `!` is a comment and the input is stdin's.
+    assert_eq!(mindsuck(&[], source, b"xyz"), b"xyz");
+    // The input only starts after the pragmas, they begin with a `!` of their own.
+    assert_eq!(
+        mindsuck(&["--self-input"], &format!("!cell 16\n{}", source), b""),
+        b"ab[c]"
+    );
+}
+
+#[test]
+fn verbose_reports_what_optimizing_did() {
+    let path = std::env::temp_dir().join(format!("mindsuck-{}-verbose.bf", std::process::id()));
+    std::fs::write(&path, "+++[-]++>>[>>]<[-]#.").unwrap();
+
+    let stderr = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .args(["--verbose", "--check"])
+            .args(extra)
+            .arg(&path)
+            .output()
+            .unwrap();
+
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    // `#` is a comment without `--debug-ops`, so it isn't counted.
+    assert_eq!(
+        stderr(&["--optimize"]).lines().next(),
+        Some("coalesced 3 runs, replaced 2 clear loops and 1 scan loops, fused 1 set-consts, reduced 19 instructions to 7")
+    );
+    assert!(!stderr(&[]).contains("coalesced"));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn histogram_counts_the_source_without_running_it() {
+    let stdout = mindsuck(&["--histogram", "--optimize"], "+++[->>+<<]#,", b"");
+
+    assert_eq!(
+        String::from_utf8(stdout).unwrap(),
+        "IncVal          4   33.3%\n\
+         IncDp           2   16.7%\n\
+         DecDp           2   16.7%\n\
+         DecVal          1    8.3%\n\
+         In              1    8.3%\n\
+         JmpFwd          1    8.3%\n\
+         JmpBck          1    8.3%\n\
+         total          12\n"
+    );
+}
+
+#[test]
+fn explain_spells_out_the_disassembly() {
+    let stdout = mindsuck(&["--explain", "--optimize"], "[-]++>>,", b"");
+
+    assert_eq!(
+        String::from_utf8(stdout).unwrap(),
+        "0000  SetConst  2       ; set the current cell to 2\n\
+         0001  IncDp     2       ; move the pointer right 2 cells\n\
+         0002  In                ; read the next input into the current cell\n\
+         0003  End               ; the end of the program\n"
+    );
+}
+
+#[test]
+fn tapes_with_no_cells_are_rejected() {
+    for args in [
+        &["--max-tape", "0"][..],
+        &["--wrap-tape", "--tape-size", "0"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .args(args)
+            .args(["--input", "", "examples/hello.bf"])
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+    }
+
+    // A pragma only warns, and the tape keeps the size it had.
+    assert_eq!(mindsuck(&[], "!maxtape 0\n+>+.", b""), [1]);
+}
+
+#[test]
+fn ook_lines_starting_with_a_bang_are_not_pragmas() {
+    assert_eq!(mindsuck(&["--dialect", "short-ook"], "!!!.\n", b""), [255]);
+}
+
+/// Builds what `--emit-c` or `--emit-rust` (`emit`) writes for each example
+/// with `build`, which gets the source and the binary to write, and checks
+/// the binary prints what the interpreter does, with and without `--optimize`.
+///
+/// Does nothing if `compiler` isn't installed.
+fn emitted_programs_match_the_interpreter(
+    emit: &str,
+    compiler: &str,
+    build: impl Fn(&std::path::Path, &std::path::Path) -> Command,
+) {
+    if Command::new(compiler).arg("--version").output().is_err() {
+        eprintln!("skipping, there is no {}", compiler);
+        return;
+    }
+
+    let dir = std::env::temp_dir().join(format!("mindsuck-{}-{}", std::process::id(), compiler));
+    let examples = [
+        ("hello", include_str!("../examples/hello.bf"), &b""[..]),
+        ("bottles", include_str!("../examples/bottles.bf"), b""),
+        (
+            "rot13",
+            include_str!("../examples/rot13.bf"),
+            b"Hello, World!\n",
+        ),
+    ];
+
+    std::fs::create_dir_all(&dir).unwrap();
+
+    for (name, source, input) in examples {
+        for optimize in [&[][..], &["--optimize"]] {
+            // rot13 needs to see EOF as something other than a 0 cell.
+            let args = [optimize, &["--eof", "unchanged"]].concat();
+            let code = dir.join(name);
+            let binary = dir.join(format!("{}.out", name));
+
+            std::fs::write(&code, mindsuck(&[&args[..], &[emit]].concat(), source, b"")).unwrap();
+            assert!(
+                build(&code, &binary).status().unwrap().success(),
+                "{}",
+                name
+            );
+
+            let mut child = Command::new(&binary)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+
+            child.stdin.take().unwrap().write_all(input).unwrap();
+            assert_eq!(
+                child.wait_with_output().unwrap().stdout,
+                mindsuck(&args, source, input),
+                "{} {:?}",
+                name,
+                optimize
+            );
+        }
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn emitted_c_matches_the_interpreter() {
+    emitted_programs_match_the_interpreter("--emit-c", "cc", |code, binary| {
+        let mut cc = Command::new("cc");
+
+        cc.args(["-x", "c", "-O1", "-o"]).arg(binary).arg(code);
+        cc
+    });
+}
+
+#[test]
+fn emitted_rust_matches_the_interpreter() {
+    emitted_programs_match_the_interpreter("--emit-rust", "rustc", |code, binary| {
+        let mut rustc = Command::new("rustc");
+
+        rustc.args(["-O", "-o"]).arg(binary).arg(code);
+        rustc
+    });
+}
+
+#[test]
+fn a_closed_stdout_is_a_write_error_not_a_panic() {
+    for args in [
+        &["--final-newline"][..],
+        &["--final-newline", "--repeat", "2"],
+    ] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .args(args)
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // The program is only read once stdin closes, so stdout is gone before anything gets written.
+        drop(child.stdout.take());
+        child.stdin.take().unwrap().write_all(b"+.").unwrap();
+
+        let output = child.wait_with_output().unwrap();
+
+        assert_eq!(output.status.code(), Some(3), "{:?}", args);
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("failed to"));
+    }
+}
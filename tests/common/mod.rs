@@ -0,0 +1,30 @@
+/// Runs `program` on the `input` bytes and asserts it writes exactly the
+/// `expected` bytes, both as written and with `optimize` on. A `Config` can be
+/// given as a fourth argument for anything else the program needs.
+macro_rules! assert_bf {
+    ($program:expr, $input:expr, $expected:expr) => {
+        assert_bf!($program, $input, $expected, mindsuck::Config::default())
+    };
+    ($program:expr, $input:expr, $expected:expr, $config:expr) => {{
+        let config: mindsuck::Config = $config;
+
+        for optimize in [mindsuck::Passes::NONE, mindsuck::Passes::ALL] {
+            let mut prog = mindsuck::Program::with_config(mindsuck::Config {
+                optimize,
+                ..config.clone()
+            });
+
+            prog.compile($program).unwrap();
+
+            let output = prog.run_with_io($input).unwrap();
+
+            assert_eq!(
+                String::from_utf8_lossy(&output),
+                String::from_utf8_lossy(&$expected[..]),
+                "optimize: {:?}",
+                optimize
+            );
+            assert_eq!(output, &$expected[..], "optimize: {:?}", optimize);
+        }
+    }};
+}
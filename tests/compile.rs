@@ -0,0 +1,478 @@
+use mindsuck::{tokens, CompileError, Config, Dialect, Instruction, Lint, Op, Passes, Program};
+use std::io;
+
+#[test]
+fn rejects_programs_longer_than_program_size() {
+    let source = "+".repeat(5000);
+    let mut prog = Program::with_config(Config {
+        program_size: 4096,
+        ..Config::default()
+    });
+
+    assert!(matches!(
+        prog.compile(&source),
+        Err(CompileError::ProgramTooLarge { limit: 4096 })
+    ));
+}
+
+#[test]
+fn comments_do_not_count_towards_program_size() {
+    let source = "+ comment ".repeat(4000);
+    let mut prog = Program::with_config(Config {
+        program_size: 4096,
+        ..Config::default()
+    });
+
+    assert!(prog.compile(&source).is_ok());
+}
+
+#[test]
+fn strict_mode_rejects_comments() {
+    let mut prog = Program::with_config(Config {
+        strict: true,
+        ..Config::default()
+    });
+
+    assert!(prog.compile("+ +\n\t[-]\r\n").is_ok());
+    assert!(matches!(
+        prog.compile("++\n+x"),
+        Err(CompileError::UnexpectedChar {
+            ch: 'x',
+            line: 2,
+            column: 2
+        })
+    ));
+}
+
+#[test]
+fn a_leading_bom_and_crlf_line_endings_are_ignored() {
+    let mut prog = Program::with_config(Config {
+        strict: true,
+        ..Config::default()
+    });
+
+    prog.compile("\u{feff}++++++++\r\n[>++++++++<-]\r\n>+.\r\n")
+        .unwrap();
+    assert_eq!(prog.run_with_io(b"").unwrap(), b"A");
+
+    // Positions are counted as if the mark was never there.
+    assert!(matches!(
+        prog.compile("\u{feff}+[\r\n+"),
+        Err(CompileError::UnclosedBracket { line: 1, column: 2 })
+    ));
+}
+
+#[test]
+fn failed_compiles_do_not_leak_open_brackets() {
+    let mut prog = Program::new();
+
+    assert!(prog.compile(&format!("[{}", "+".repeat(5000))).is_err());
+    assert!(matches!(
+        prog.compile("+]"),
+        Err(CompileError::UnmatchedBracket { line: 1, column: 2 })
+    ));
+}
+
+#[test]
+fn bracket_errors_point_at_the_brackets_around_them() {
+    let related = |source: &str| {
+        let e = Program::new().compile(source).unwrap_err();
+
+        (e.position(), e.related_brackets(source))
+    };
+
+    assert_eq!(related("+[>[-]\n<[+]]]"), (Some((2, 6)), vec![(1, 2)]));
+    assert_eq!(related("+]"), (Some((1, 2)), vec![]));
+    assert_eq!(related("[>[-\n[+<"), (Some((2, 1)), vec![(1, 1), (1, 3)]));
+    assert_eq!(related("[[]"), (Some((1, 1)), vec![]));
+}
+
+#[test]
+fn nesting_deeper_than_the_stack_is_an_error() {
+    let source = format!("{}{}", "[".repeat(600), "]".repeat(600));
+    let mut prog = Program::new();
+
+    assert!(matches!(
+        prog.compile(&source),
+        Err(CompileError::StackOverflow {
+            line: 1,
+            column: 513,
+            limit: 512
+        })
+    ));
+}
+
+#[test]
+fn programs_exactly_program_size_long_still_end() {
+    let mut prog = Program::with_config(Config {
+        program_size: 4096,
+        ..Config::default()
+    });
+
+    assert!(prog.compile(&format!("{}\n", ".".repeat(4096))).is_ok());
+    assert_eq!(prog.run_with_io(b"").unwrap(), vec![0; 4096]);
+
+    assert!(matches!(
+        prog.compile(&".".repeat(4097)),
+        Err(CompileError::ProgramTooLarge { limit: 4096 })
+    ));
+}
+
+#[test]
+fn programs_have_no_size_limit_by_default() {
+    let source = ">+".repeat(50_000) + &".<".repeat(50_000);
+    let mut prog = Program::new();
+
+    prog.compile(&source).unwrap();
+    assert_eq!(prog.instructions().len(), 200_000);
+    assert_eq!(prog.run_with_io(b"").unwrap(), vec![1; 50_000]);
+}
+
+#[test]
+fn program_and_stack_sizes_come_from_the_config() {
+    let mut prog = Program::with_config(Config {
+        program_size: 100_000,
+        stack_size: 2,
+        ..Config::default()
+    });
+
+    assert!(prog.compile(&".".repeat(100_000)).is_ok());
+    assert!(matches!(
+        prog.compile(&".".repeat(100_001)),
+        Err(CompileError::ProgramTooLarge { limit: 100_000 })
+    ));
+    assert!(prog.compile("[[]]").is_ok());
+    assert!(matches!(
+        prog.compile("[[[]]]"),
+        Err(CompileError::StackOverflow {
+            line: 1,
+            column: 3,
+            limit: 2
+        })
+    ));
+}
+
+#[test]
+fn the_bracket_stack_grows_up_to_stack_size() {
+    let depth = 100_000;
+    let source = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let mut prog = Program::with_config(Config {
+        program_size: 2 * depth,
+        stack_size: usize::MAX,
+        ..Config::default()
+    });
+
+    prog.compile(&source).unwrap();
+    assert_eq!(prog.run_with_io(b"").unwrap(), b"");
+
+    let mut prog = Program::with_config(Config {
+        program_size: 2 * depth,
+        stack_size: depth - 1,
+        ..Config::default()
+    });
+
+    assert!(matches!(
+        prog.compile(&source),
+        Err(CompileError::StackOverflow {
+            line: 1,
+            column,
+            limit
+        }) if column == depth && limit == depth - 1
+    ));
+}
+
+#[test]
+fn validate_reports_the_same_bracket_errors_as_compile() {
+    for source in ["+[\n  ++]]", "+\n +[[\n]", "[[]", "ok [-]"] {
+        let compiled = Program::new().compile(source).map_err(|e| e.position());
+
+        assert_eq!(
+            Program::validate(source).map_err(|e| e.position()),
+            compiled
+        );
+    }
+}
+
+#[test]
+fn ook_translates_to_brainfuck_in_place() {
+    let ook = "Ook. Ook. Ook! Ook?\nOok! Ook!Ook? Ook! Ook! Ook.";
+
+    assert_eq!(
+        Dialect::Ook.translate(ook).unwrap(),
+        "+         [        \n-        ]         .        "
+    );
+    assert_eq!(
+        Dialect::ShortOok.translate(". . !?\n!! ?! !.").unwrap(),
+        "+   [ \n-  ]  . "
+    );
+    assert!(matches!(
+        Dialect::Ook.translate("Ook. Ook.\n  Ook? Ook?"),
+        Err(CompileError::MalformedOok { line: 2, column: 3 })
+    ));
+    assert!(matches!(
+        Dialect::Ook.translate("Ook. Ook. Ook!"),
+        Err(CompileError::MalformedOok {
+            line: 1,
+            column: 11
+        })
+    ));
+    assert!(matches!(
+        Dialect::ShortOok.translate(". x"),
+        Err(CompileError::MalformedOok { line: 1, column: 3 })
+    ));
+}
+
+#[test]
+fn instructions_show_the_jump_table() {
+    let mut prog = Program::with_config(Config {
+        optimize: Passes::ALL,
+        ..Config::default()
+    });
+
+    prog.compile("++[->>+<<]>[-]").unwrap();
+
+    let listing = prog
+        .instructions()
+        .iter()
+        .map(|&Instruction { operator, operand }| (operator, operand))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        listing,
+        [
+            (Op::IncVal, 2),
+            (Op::JmpFwd, 6),
+            (Op::DecVal, 1),
+            (Op::IncDp, 2),
+            (Op::IncVal, 1),
+            (Op::DecDp, 2),
+            (Op::JmpBck, 1),
+            (Op::IncDp, 1),
+            (Op::Clear, 0),
+        ]
+    );
+}
+
+#[test]
+fn clears_fuse_with_the_adds_after_them() {
+    let listing = |optimize| {
+        let mut prog = Program::with_config(Config {
+            optimize,
+            ..Config::default()
+        });
+
+        prog.compile("[-]+++>[-]--<[-]").unwrap();
+        prog.instructions()
+            .iter()
+            .map(|&Instruction { operator, operand }| (operator, operand))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        listing(Passes::ALL),
+        [
+            (Op::SetConst, 3),
+            (Op::IncDp, 1),
+            (Op::SetConst, usize::MAX - 1),
+            (Op::DecDp, 1),
+            (Op::Clear, 0),
+        ]
+    );
+    // Without the pass the clears stay clears.
+    assert_eq!(
+        listing(Passes {
+            set: false,
+            ..Passes::ALL
+        })[..2],
+        [(Op::Clear, 0), (Op::IncVal, 3)]
+    );
+}
+
+#[test]
+fn tokens_classify_characters_like_the_compiler() {
+    let source = "a+ [>é,.<]\n-#";
+
+    assert_eq!(
+        tokens(source).collect::<Vec<_>>(),
+        [
+            (1, Op::IncVal),
+            (3, Op::JmpFwd),
+            (4, Op::IncDp),
+            (7, Op::In),
+            (8, Op::Out),
+            (9, Op::DecDp),
+            (10, Op::JmpBck),
+            (12, Op::DecVal),
+            (13, Op::Dump),
+        ]
+    );
+    assert_eq!(Op::from_char('x'), None);
+
+    let mut prog = Program::with_config(Config {
+        debug_ops: true,
+        ..Config::default()
+    });
+
+    prog.compile(source).unwrap();
+    assert!(prog
+        .instructions()
+        .iter()
+        .map(|instruction| instruction.operator)
+        .eq(tokens(source).map(|(_, op)| op)));
+}
+
+#[test]
+fn lint_finds_instructions_that_cancel_out() {
+    let lints = Program::lint("++-[>< comment\n<\n>.]+#- +-+");
+
+    assert_eq!(
+        lints,
+        [
+            Lint::CancelsOut {
+                ops: "+-",
+                line: 1,
+                column: 2
+            },
+            Lint::CancelsOut {
+                ops: "><",
+                line: 1,
+                column: 5
+            },
+            Lint::CancelsOut {
+                ops: "<>",
+                line: 2,
+                column: 1
+            },
+            Lint::CancelsOut {
+                ops: "-+",
+                line: 3,
+                column: 6
+            },
+            Lint::CancelsOut {
+                ops: "-+",
+                line: 3,
+                column: 9
+            },
+        ]
+    );
+    assert_eq!(lints[0].to_string(), "'+-' does nothing");
+    assert!(Program::lint("+[-]>+<.").is_empty());
+}
+
+#[test]
+fn lint_finds_empty_loops() {
+    let lints = Program::lint("+[-]>[]<[ a comment\n]+[[]]");
+
+    assert_eq!(
+        lints,
+        [
+            Lint::EmptyLoop { line: 1, column: 6 },
+            Lint::EmptyLoop { line: 1, column: 9 },
+            Lint::EmptyLoop { line: 2, column: 4 },
+        ]
+    );
+    assert_eq!(
+        lints[0].to_string(),
+        "'[]' never ends unless the cell is already 0"
+    );
+}
+
+#[test]
+fn compiled_programs_round_trip_through_bfc() {
+    let mut prog = Program::with_config(Config {
+        optimize: Passes::ALL,
+        ..Config::default()
+    });
+
+    prog.compile(include_str!("../examples/bottles.bf"))
+        .unwrap();
+
+    let mut saved = Vec::new();
+
+    prog.save_compiled(&mut saved).unwrap();
+
+    let mut loaded = Program::new();
+
+    loaded.load_compiled(&mut &saved[..]).unwrap();
+    assert_eq!(loaded.instructions(), prog.instructions());
+    assert_eq!(
+        loaded.run_with_io(b"").unwrap(),
+        prog.run_with_io(b"").unwrap()
+    );
+
+    // A SetConst below zero is saved wrapped to the cell, so it loads again.
+    prog.compile("[-]--.").unwrap();
+    saved.clear();
+    prog.save_compiled(&mut saved).unwrap();
+    loaded.load_compiled(&mut &saved[..]).unwrap();
+    assert_eq!(loaded.run_with_io(b"").unwrap(), [254]);
+
+    let invalid = |bytes: &[u8]| {
+        let mut prog = Program::with_config(Config {
+            program_size: 4096,
+            ..Config::default()
+        });
+
+        prog.compile("+++").unwrap();
+
+        let kind = prog.load_compiled(&mut &bytes[..]).unwrap_err().kind();
+
+        assert_eq!(prog.instructions().len(), 3);
+        kind
+    };
+    let bfc = |ops: &[(u8, u64)]| {
+        let mut bytes = b"BFC\0\x01".to_vec();
+
+        bytes.extend((ops.len() as u64).to_le_bytes());
+
+        for &(op, operand) in ops {
+            bytes.push(op);
+            bytes.extend(operand.to_le_bytes());
+        }
+
+        bytes
+    };
+    let jmp_fwd = Op::JmpFwd as u8;
+    let jmp_bck = Op::JmpBck as u8;
+
+    assert_eq!(invalid(b"++[-]"), io::ErrorKind::InvalidData);
+    assert_eq!(invalid(b"BFC\0\x02"), io::ErrorKind::InvalidData);
+    assert_eq!(invalid(&saved[..20]), io::ErrorKind::UnexpectedEof);
+    assert_eq!(invalid(&bfc(&[(0, 0)])), io::ErrorKind::InvalidData);
+    assert_eq!(invalid(&bfc(&[(99, 0)])), io::ErrorKind::InvalidData);
+    // Operands no compile would ever give.
+    for op in [
+        Op::IncDp,
+        Op::DecDp,
+        Op::IncVal,
+        Op::DecVal,
+        Op::ScanRight,
+        Op::ScanLeft,
+    ] {
+        assert_eq!(invalid(&bfc(&[(op as u8, 0)])), io::ErrorKind::InvalidData);
+    }
+    assert_eq!(
+        invalid(&bfc(&[(Op::SetConst as u8, 256)])),
+        io::ErrorKind::InvalidData
+    );
+    assert!(Program::new()
+        .load_compiled(&mut &bfc(&[(Op::SetConst as u8, 255)])[..])
+        .is_ok());
+    // Brackets that cross instead of nesting.
+    assert_eq!(
+        invalid(&bfc(&[
+            (jmp_fwd, 2),
+            (jmp_fwd, 3),
+            (jmp_bck, 0),
+            (jmp_bck, 1)
+        ])),
+        io::ErrorKind::InvalidData
+    );
+    assert_eq!(
+        invalid(&bfc(&[(Op::IncVal as u8, 1); 4097])),
+        io::ErrorKind::InvalidData
+    );
+    assert!(Program::new()
+        .load_compiled(&mut &bfc(&[(jmp_fwd, 1), (jmp_bck, 0)])[..])
+        .is_ok());
+}
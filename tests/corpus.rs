@@ -0,0 +1,78 @@
+use mindsuck::{Config, Dispatch, Passes, Program};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Every `name.bf` in `tests/corpus`, which comes with the `name.out` it has to
+/// print and, if it reads anything, a `name.in` to read.
+fn corpus() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut programs = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "bf"))
+        .collect::<Vec<_>>();
+
+    programs.sort();
+    programs
+}
+
+fn expected(path: &Path) -> (Vec<u8>, Vec<u8>) {
+    let input = fs::read(path.with_extension("in")).unwrap_or_default();
+    let output = fs::read(path.with_extension("out")).unwrap();
+
+    (input, output)
+}
+
+#[test]
+fn the_corpus_runs_the_same_however_it_is_compiled() {
+    let programs = corpus();
+
+    assert!(!programs.is_empty());
+
+    for path in programs {
+        let source = fs::read_to_string(&path).unwrap();
+        let (input, output) = expected(&path);
+
+        for optimize in [Passes::NONE, Passes::ALL] {
+            for dispatch in [Dispatch::Table, Dispatch::Match] {
+                let mut prog = Program::with_config(Config {
+                    optimize,
+                    dispatch,
+                    ..Config::default()
+                });
+
+                prog.compile(&source).unwrap();
+                assert_eq!(
+                    prog.run_with_io(&input).unwrap(),
+                    output,
+                    "{} with {:?} and {:?}",
+                    path.display(),
+                    optimize,
+                    dispatch
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_corpus_runs_through_the_binary() {
+    for path in corpus() {
+        let (input, output) = expected(&path);
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mindsuck"))
+            .arg("--optimize")
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(&input).unwrap();
+
+        let result = child.wait_with_output().unwrap();
+
+        assert!(result.status.success(), "{}", path.display());
+        assert_eq!(result.stdout, output, "{}", path.display());
+    }
+}
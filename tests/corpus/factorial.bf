@@ -0,0 +1,22 @@
+Prints the factorials of 1 to 5 as in 5! = 120
+
+[-]+>>[-]+++++[<+[->>+>+<<<]>>>[-<<<+>>>]<[-<<<[-
+>>>>>+<+<<<<]>>>>[-<<<<+>>>>]<]<<<[-]>>>>>[-<<<<<+>>>>>]<<<<[-
+>>>>>>>>>+>+<<<<<<<<<<]>>>>>>>>>>[-<<<<<<<<<<+>>>>>>>>>>][-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->>>+<<<]>>>>[-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->+>+<<]>>[-
+<<+>>]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<<[-
+>+>+<<]>>[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>
+>[-]]<[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>>[-
+]]<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.[-
+]>>>>>>[-]>[-]<<<<<<<<<<<<+++++++++++++++++++++++++++++++++.-
+.+++++++++++++++++++++++++++++.-----------------------------.---
+-----------------------------<<<<<<<[-
+>>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>][-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->>>+<<<]>>>>[-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->+>+<<]>>[-
+<<+>>]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<<[-
+>+>+<<]>>[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>
+>[-]]<[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>>[-
+]]<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.[-
+]>>>>>>[-]>[-]<<<<<<<<<<<<++++++++++.----------<<<<<-]
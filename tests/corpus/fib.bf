@@ -0,0 +1,12 @@
+Prints the Fibonacci numbers up to 233 one per line
+
+[-]+>[-]+>>[-]+++++++++++++[<<<[-
+>>>>>>>>>>+>+<<<<<<<<<<<]>>>>>>>>>>>[-<<<<<<<<<<<+>>>>>>>>>>>][-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->>>+<<<]>>>>[-
+]++++++++++<[->-[>+>>]>[+[-<+>]>+>>]<<<<<]>[-]>>[->+>+<<]>>[-
+<<+>>]<<<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<<[-
+>+>+<<]>>[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>
+>[-]]<[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>>[-
+]]<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.[-
+]>>>>>>[-]>[-]<<<<<<<<<<<<<<++++++++++.[-]<<<<<[->>+<<]>[-
+>+>>+<<<]>>>[-<<<+>>>]<<<[-<+>]>[-<+>]>-]
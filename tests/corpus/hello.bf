@@ -0,0 +1,9 @@
+Prints Hello and World with a comma and an exclamation mark
+
+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
+++++++++.+++++++++++++++++++++++++++++.+++++++..+++.------------
+-------------------------------------------------------.--------
+----.+++++++++++++++++++++++++++++++++++++++++++++++++++++++.+++
++++++++++++++++++++++.+++.------.--------.----------------------
+---------------------------------------------.------------------
+-----.----------
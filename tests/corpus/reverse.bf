@@ -0,0 +1,4 @@
+Reads a line and prints it back to front followed by a newline
+The first cell is left empty to stop on
+
+>,----------[++++++++++>,----------]<[.<]++++++++++.
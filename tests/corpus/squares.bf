@@ -0,0 +1,12 @@
+Prints the squares of 0 to 15 one per line
+
+>[-]++++++++++++++++[<[->>>+>+<<<<]>>>>[-<<<<+>>>>]<[-<<<[-
+>>+>>+<<<<]>>>>[-<<<<+>>>>]<]<[->>>>>>>>+>+<<<<<<<<<]>>>>>>>>>[-
+<<<<<<<<<+>>>>>>>>>][-]++++++++++<[->-[>+>>]>[+[-
+<+>]>+>>]<<<<<]>[-]>>[->>>+<<<]>>>>[-]++++++++++<[->-[>+>>]>[+[-
+<+>]>+>>]<<<<<]>[-]>>[->+>+<<]>>[-<<+>>]<<<[->>>+>+<<<<]>>>>[-
+<<<<+>>>>]<<[-
+>+>+<<]>>[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>
+>[-]]<[<<<++++++++++++++++++++++++++++++++++++++++++++++++.>>>[-
+]]<<<<<<<<<++++++++++++++++++++++++++++++++++++++++++++++++.[-
+]>>>>>>[-]>[-]<<<<<<<<<<<<<<++++++++++.[-]<<<[-]<<+>-]
@@ -0,0 +1,202 @@
+use mindsuck::embedded::Machine;
+use mindsuck::{
+    CellWidth, CompileError, Config, Dispatch, Endian, EofMode, InterpreterError, Passes,
+    PointerPolicy, Program,
+};
+
+#[test]
+fn machine_matches_program_output() {
+    let source = std::fs::read_to_string("examples/hello.bf").unwrap();
+    let mut machine = Machine::<1024, 64, 16>::new(Config::default());
+    let mut buffer = [0u8; 64];
+    let mut out = &mut buffer[..];
+
+    machine.compile(&source).unwrap();
+    machine.execute(&mut &b""[..], &mut out).unwrap();
+
+    let written = 64 - out.len();
+    let mut prog = Program::new();
+
+    prog.compile(&source).unwrap();
+    assert_eq!(buffer[..written], prog.run_with_io(b"").unwrap());
+}
+
+#[test]
+fn machine_and_both_program_loops_run_the_same() {
+    let tape = |pointer| Config {
+        tape_size: 64,
+        max_tape: 64,
+        pointer,
+        ..Config::default()
+    };
+    let cases = [
+        (
+            include_str!("../examples/hello.bf"),
+            tape(PointerPolicy::Error),
+        ),
+        (
+            include_str!("../examples/rot13.bf"),
+            Config {
+                eof: EofMode::Unchanged,
+                ..tape(PointerPolicy::Error)
+            },
+        ),
+        (
+            include_str!("../examples/bottles.bf"),
+            Config {
+                optimize: Passes::ALL,
+                ..tape(PointerPolicy::Error)
+            },
+        ),
+        (
+            ",[.,]>+[>]<<[<]",
+            Config {
+                optimize: Passes::ALL,
+                ..tape(PointerPolicy::Clamp)
+            },
+        ),
+        ("+[<-]>>[-]+++[>+++<-]", tape(PointerPolicy::Wrap)),
+        (",+[>+]", tape(PointerPolicy::Error)),
+        ("<", tape(PointerPolicy::Error)),
+        (
+            "-.,+[-.,+]",
+            Config {
+                cell_width: CellWidth::U16,
+                eof: EofMode::NegativeOne,
+                ..tape(PointerPolicy::Error)
+            },
+        ),
+        (
+            "+[>+]",
+            Config {
+                max_steps: Some(1000),
+                ..tape(PointerPolicy::Wrap)
+            },
+        ),
+    ];
+
+    for (source, config) in cases {
+        let mut machine = Machine::<4096, 64, 64>::new(config.clone());
+        let mut buffer = [0u8; 1 << 14];
+        let mut out = &mut buffer[..];
+
+        machine.compile(source).unwrap();
+
+        let result = machine.execute(&mut &b"abc"[..], &mut out);
+        let written = (1 << 14) - out.len();
+        let expected = (
+            format!("{:?}", result),
+            buffer[..written].to_vec(),
+            machine.tape().to_vec(),
+        );
+
+        for dispatch in [Dispatch::Match, Dispatch::Table] {
+            let mut prog = Program::with_config(Config {
+                dispatch,
+                ..config.clone()
+            });
+            let mut output = Vec::new();
+
+            prog.compile(source).unwrap();
+
+            let result = prog.execute_with(&mut &b"abc"[..], &mut output).map(|_| ());
+            let mut tape = vec![0; 64];
+
+            for (cell, value) in prog.tape().non_zero() {
+                tape[cell] = value;
+            }
+
+            assert_eq!(
+                (format!("{:?}", result), output, tape),
+                expected,
+                "{:?} {}",
+                dispatch,
+                source
+            );
+        }
+    }
+}
+
+#[test]
+fn machine_reads_input_and_stops_at_eof() {
+    let mut machine = Machine::<64, 8, 4>::new(Config {
+        optimize: Passes::ALL,
+        ..Config::default()
+    });
+    let mut buffer = [0u8; 8];
+
+    machine.compile(",[.,]").unwrap();
+    machine
+        .execute(&mut &b"cat"[..], &mut &mut buffer[..])
+        .unwrap();
+
+    assert_eq!(&buffer[..3], b"cat");
+}
+
+#[test]
+fn machine_reads_wide_input_little_endian() {
+    let mut machine = Machine::<64, 8, 4>::new(Config {
+        cell_width: CellWidth::U16,
+        wide_input: true,
+        input_endian: Endian::Little,
+        wide_output: true,
+        ..Config::default()
+    });
+    let mut buffer = [0u8; 2];
+
+    machine.compile(",.").unwrap();
+    machine
+        .execute(&mut &[0x12, 0x34][..], &mut &mut buffer[..])
+        .unwrap();
+
+    assert_eq!(buffer, [0x34, 0x12]);
+}
+
+#[test]
+fn machine_limits_come_from_its_arrays() {
+    let mut machine = Machine::<4, 2, 1>::new(Config::default());
+
+    assert!(matches!(
+        machine.compile("++++"),
+        Err(CompileError::ProgramTooLarge { limit: 3 })
+    ));
+    assert!(matches!(
+        machine.compile("[[]]"),
+        Err(CompileError::StackOverflow { limit: 1, .. })
+    ));
+    assert!(matches!(
+        machine.compile("+[+"),
+        Err(CompileError::UnclosedBracket { line: 1, column: 2 })
+    ));
+
+    machine.compile(">>").unwrap();
+    assert!(matches!(
+        machine.execute(&mut &b""[..], &mut &mut [0u8; 0][..]),
+        Err(InterpreterError::PointerOutOfBounds { pc: 1, ptr: 1 })
+    ));
+
+    let mut wrapping = Machine::<4, 2, 1>::new(Config {
+        pointer: PointerPolicy::Wrap,
+        ..Config::default()
+    });
+
+    wrapping.compile("<+").unwrap();
+    wrapping
+        .execute(&mut &b""[..], &mut &mut [0u8; 0][..])
+        .unwrap();
+    assert_eq!(wrapping.tape(), [0, 1]);
+
+    // A scan round a tape with nowhere to stop still runs into the step limit.
+    let mut scanning = Machine::<16, 2, 1>::new(Config {
+        pointer: PointerPolicy::Wrap,
+        optimize: Passes::ALL,
+        max_steps: Some(1000),
+        ..Config::default()
+    });
+
+    scanning.compile("+>+[>]").unwrap();
+    assert!(matches!(
+        scanning.execute(&mut &b""[..], &mut &mut [0u8; 0][..]),
+        Err(InterpreterError::StepLimitExceeded)
+    ));
+}
@@ -0,0 +1,888 @@
+use mindsuck::{
+    BfInput, CellWidth, Comparison, Condition, Config, Dispatch, Endian, InterpreterError, Op,
+    OutputEncoding, Passes, PointerPolicy, Program, RunStats, StepResult, Tape,
+    TIMEOUT_CHECK_STEPS,
+};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+fn run(source: &str, config: Config) -> Result<(), InterpreterError> {
+    let mut prog = Program::with_config(config);
+
+    prog.compile(source)?;
+    prog.execute().map(|_| ())
+}
+
+fn small_tape(pointer: PointerPolicy) -> Config {
+    Config {
+        tape_size: 4,
+        max_tape: 4,
+        pointer,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn moving_left_of_the_first_cell_is_an_error_by_default() {
+    assert!(matches!(
+        run("+<", Config::default()),
+        Err(InterpreterError::PointerOutOfBounds { pc: 1, ptr: 0 })
+    ));
+}
+
+#[test]
+fn moving_right_of_the_last_cell_is_an_error_by_default() {
+    assert!(matches!(
+        run(">>>>", small_tape(PointerPolicy::Error)),
+        Err(InterpreterError::PointerOutOfBounds { pc: 3, ptr: 3 })
+    ));
+}
+
+#[test]
+fn moving_off_the_tape_reports_where_the_pointer_was() {
+    for dispatch in [Dispatch::Match, Dispatch::Table] {
+        let config = Config {
+            optimize: Passes::ALL,
+            dispatch,
+            ..small_tape(PointerPolicy::Error)
+        };
+
+        // Coalesced, each of these is one move that starts well inside the tape.
+        assert!(matches!(
+            run(">>>>>>", config.clone()),
+            Err(InterpreterError::PointerOutOfBounds { pc: 0, ptr: 0 })
+        ));
+        assert!(matches!(
+            run(">><<<", config),
+            Err(InterpreterError::PointerOutOfBounds { pc: 1, ptr: 2 })
+        ));
+    }
+}
+
+#[test]
+fn finishing_on_the_last_cell_is_not_an_error() {
+    let mut prog = Program::with_config(small_tape(PointerPolicy::Error));
+
+    prog.compile(">>>+").unwrap();
+
+    let stats = prog.execute_with(&mut &b""[..], &mut Vec::new()).unwrap();
+
+    assert_eq!((stats.steps, stats.final_ptr), (4, 3));
+
+    // Only actually stepping off the end is, wherever the program ends.
+    prog.compile(">>>>+").unwrap();
+    assert!(matches!(
+        prog.execute_with(&mut &b""[..], &mut Vec::new()),
+        Err(InterpreterError::PointerOutOfBounds { pc: 3, ptr: 3 })
+    ));
+}
+
+#[test]
+fn wrap_moves_to_the_other_end_of_the_tape() {
+    // Both loops only terminate once the pointer lands on a zeroed cell across the edge.
+    assert!(run("+[<]", small_tape(PointerPolicy::Wrap)).is_ok());
+    assert!(run(">+>+>+[>]", small_tape(PointerPolicy::Wrap)).is_ok());
+}
+
+#[test]
+fn a_wrapped_tape_is_a_ring_of_exactly_its_size() {
+    for optimize in [Passes::NONE, Passes::ALL] {
+        let mut prog = Program::with_config(Config {
+            tape_size: 5,
+            max_tape: 5,
+            pointer: PointerPolicy::Wrap,
+            optimize,
+            ..Config::default()
+        });
+
+        // Round the left edge to cell 3, seven to the right is back on 0 and
+        // twelve to the left lands on 3 again.
+        prog.compile("+<<+>>>>>>>+<<<<<<<<<<<<+").unwrap();
+        prog.run_with_io(b"").unwrap();
+
+        assert_eq!(prog.tape().len(), 5);
+        assert_eq!(prog.tape().non_zero(), [(0, 2), (3, 2)], "{:?}", optimize);
+        assert_eq!(prog.pointer(), 3);
+    }
+}
+
+#[test]
+fn wrapping_goes_round_max_tape_either_way() {
+    for optimize in [Passes::NONE, Passes::ALL] {
+        let mut prog = Program::with_config(Config {
+            tape_size: 2,
+            max_tape: 6,
+            pointer: PointerPolicy::Wrap,
+            optimize,
+            ..Config::default()
+        });
+
+        // Off the left edge is the last cell the tape could grow to, not the
+        // last one it has so far, so coming back lands where it started.
+        prog.compile("+<+>+").unwrap();
+        prog.run_with_io(b"").unwrap();
+
+        assert_eq!(prog.tape().len(), 6);
+        assert_eq!(prog.tape().non_zero(), [(0, 2), (5, 1)], "{:?}", optimize);
+        assert_eq!(prog.pointer(), 0);
+    }
+}
+
+#[test]
+fn scans_with_nowhere_to_stop_count_their_moves_as_steps() {
+    for pointer in [PointerPolicy::Wrap, PointerPolicy::Clamp] {
+        for dispatch in [Dispatch::Match, Dispatch::Table] {
+            let mut prog = Program::with_config(Config {
+                optimize: Passes::ALL,
+                max_steps: Some(1000),
+                dispatch,
+                ..small_tape(pointer)
+            });
+
+            prog.compile("+>+>+>+[>]").unwrap();
+            assert!(matches!(
+                prog.execute_with(&mut &b""[..], &mut Vec::new()),
+                Err(InterpreterError::StepLimitExceeded)
+            ));
+
+            // Stepping gets one move further each time instead of hanging.
+            prog.reset();
+
+            for _ in 0..20 {
+                prog.step().unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+fn clamp_keeps_the_pointer_on_the_edge() {
+    // A clamped pointer stays on the cell it just cleared instead of falling off the tape.
+    assert!(run("+<<<-[<]", small_tape(PointerPolicy::Clamp)).is_ok());
+    assert!(run(">>>>>>+[-<]", small_tape(PointerPolicy::Clamp)).is_ok());
+}
+
+#[test]
+fn sparse_tapes_only_allocate_what_is_written() {
+    let mut prog = Program::with_config(Config {
+        tape_size: 1 << 40,
+        max_tape: 1 << 40,
+        sparse: true,
+        optimize: Passes::ALL,
+        ..Config::default()
+    });
+
+    prog.compile(&format!("+{}+{}.", ">".repeat(10000), "<".repeat(10000)))
+        .unwrap();
+    assert_eq!(prog.run_with_io(b"").unwrap(), [1]);
+    assert_eq!(prog.tape().len(), 1 << 40);
+    assert_eq!(prog.tape().non_zero(), [(0, 1), (10000, 1)]);
+
+    match prog.tape() {
+        Tape::Sparse { pages, .. } => assert_eq!(pages.len(), 2),
+        Tape::Dense(_) => panic!("expected a sparse tape"),
+    }
+}
+
+#[test]
+fn tapes_answer_none_past_the_end() {
+    for sparse in [false, true] {
+        let mut tape = Tape::new(4, sparse);
+
+        *tape.get_mut(3).unwrap() = 7;
+        assert_eq!(tape.get(3), Some(7));
+        assert_eq!(tape.get(4), None);
+        assert!(tape.get_mut(4).is_none());
+    }
+}
+
+#[test]
+fn init_cell_fills_the_tape_as_it_grows() {
+    for sparse in [false, true] {
+        let run = |init_cell, tape_size, source: &str| {
+            let mut prog = Program::with_config(Config {
+                init_cell,
+                tape_size,
+                sparse,
+                ..Config::default()
+            });
+
+            prog.compile(source).unwrap();
+            prog.run_with_io(b"").unwrap()
+        };
+
+        // Loops still stop on 0, whatever the tape started out as.
+        assert_eq!(run(3, 30000, "[.-]"), [3, 2, 1]);
+        assert_eq!(run(7, 2, ">>>>>."), [7]);
+        // Cut down to the cell width.
+        assert_eq!(run(0x1ff, 30000, "."), [0xff]);
+        assert_eq!(run(0, 30000, "[.-]"), []);
+
+        // Only the cells the program changed stand out from the fill.
+        let mut prog = Program::with_config(Config {
+            init_cell: 170,
+            sparse,
+            ..Config::default()
+        });
+
+        prog.compile(">+>>[-]").unwrap();
+        prog.run_with_io(b"").unwrap();
+        assert_eq!(prog.tape().changed(170), [(1, 171), (3, 0)]);
+        assert_eq!(prog.tape().changed(0).len(), prog.tape().len() - 1);
+    }
+}
+
+#[test]
+fn sparse_and_dense_tapes_run_the_same() {
+    let programs = [
+        (PointerPolicy::Wrap, ",+[<]>>+>+[>]."),
+        (PointerPolicy::Clamp, "+<<<-[<]>>>>>>+[-<],."),
+    ];
+
+    for (pointer, source) in programs {
+        let run = |sparse| {
+            let mut prog = Program::with_config(Config {
+                sparse,
+                optimize: Passes::ALL,
+                ..small_tape(pointer)
+            });
+
+            prog.compile(source).unwrap();
+            (
+                prog.run_with_io(b"x").unwrap(),
+                prog.tape().iter().collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(run(true), run(false));
+    }
+}
+
+#[test]
+fn infinite_loops_stop_at_the_step_limit() {
+    let config = Config {
+        max_steps: Some(1000),
+        ..Config::default()
+    };
+
+    assert!(matches!(
+        run("+[]", config.clone()),
+        Err(InterpreterError::StepLimitExceeded)
+    ));
+    assert!(run("+++[-]", config).is_ok());
+}
+
+#[test]
+fn infinite_loops_stop_at_the_timeout() {
+    let config = Config {
+        timeout: Some(Duration::from_millis(50)),
+        ..Config::default()
+    };
+    let start = Instant::now();
+
+    match run("+[]", config.clone()) {
+        Err(InterpreterError::Timeout { steps }) => {
+            assert!(steps > 0 && steps.is_multiple_of(TIMEOUT_CHECK_STEPS))
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(run("+++[-]", config).is_ok());
+}
+
+#[test]
+fn folded_loops_count_for_the_loop_depth_limit() {
+    for optimize in [Passes::NONE, Passes::ALL] {
+        for dispatch in [Dispatch::Match, Dispatch::Table] {
+            let config = Config {
+                max_loop_depth: Some(1),
+                optimize,
+                dispatch,
+                ..Config::default()
+            };
+
+            for (source, at) in [("+[[-]]", 2), ("+[>+[<]]", 4), ("+[[-]+]", 2)] {
+                assert!(
+                    matches!(
+                        run(source, config.clone()),
+                        Err(InterpreterError::LoopDepthExceeded { pc }) if pc == at
+                    ),
+                    "{} {:?}",
+                    source,
+                    optimize
+                );
+            }
+
+            // A loop that is skipped over was never entered, folded or not.
+            assert!(run("+[>[-]<-]", config).is_ok());
+        }
+    }
+}
+
+#[test]
+fn loops_nested_past_the_limit_are_an_error() {
+    let config = Config {
+        max_loop_depth: Some(2),
+        ..Config::default()
+    };
+
+    assert!(matches!(
+        run("+[[[-]]]", config.clone()),
+        Err(InterpreterError::LoopDepthExceeded { pc: 3 })
+    ));
+    assert!(run("+[[-]]+[[-]]", config.clone()).is_ok());
+    assert!(run("[[[[-]]]]", config.clone()).is_ok());
+
+    // Stepping in and out of a run keeps count of how deep it already is.
+    let mut prog = Program::with_config(config);
+
+    prog.compile("+[[[-]]]").unwrap();
+    prog.step().unwrap();
+    prog.step().unwrap();
+
+    let state = prog.snapshot();
+
+    prog.reset();
+    prog.restore(state);
+    prog.step().unwrap();
+    assert!(matches!(
+        prog.step(),
+        Err(InterpreterError::LoopDepthExceeded { pc: 3 })
+    ));
+}
+
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn errors_say_what_went_wrong_and_where() {
+    assert_eq!(
+        run("+<", Config::default()).unwrap_err().to_string(),
+        "instruction 1 moved the pointer off the tape from cell 0"
+    );
+
+    let mut prog = Program::new();
+
+    prog.compile("+.").unwrap();
+    assert!(matches!(
+        prog.execute_with(&mut &b""[..], &mut Broken),
+        Err(InterpreterError::FailedToWrite { pc: Some(1) })
+    ));
+
+    // Both kinds of error fit in a `Box<dyn Error>`, so `?` works on them.
+    let error: Box<dyn Error> = run("[", Config::default()).unwrap_err().into();
+
+    assert_eq!(error.to_string(), "unclosed '['");
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn run_with_io_captures_output() {
+    let mut prog = Program::new();
+
+    prog.compile(",+.,+.").unwrap();
+
+    assert_eq!(prog.run_with_io(b"HA").unwrap(), b"IB");
+}
+
+#[test]
+fn out_writes_nul_and_control_bytes_as_they_are() {
+    let source = format!(
+        ".{}.{}.{}.",
+        "+".repeat(10),
+        "+".repeat(17),
+        "+".repeat(228)
+    );
+    let mut prog = Program::new();
+
+    prog.compile(&source).unwrap();
+    assert_eq!(prog.run_with_io(b"").unwrap(), [0, 10, 27, 255]);
+
+    // Wider cells still only write their low byte.
+    let mut prog = Program::with_config(Config {
+        cell_width: CellWidth::U16,
+        ..Config::default()
+    });
+
+    prog.compile(&format!("{}.", "+".repeat(0x1ff))).unwrap();
+    assert_eq!(prog.run_with_io(b"").unwrap(), [255]);
+}
+
+#[test]
+fn execute_reports_run_stats() {
+    let mut prog = Program::new();
+
+    prog.compile("++[>.+<-]>").unwrap();
+
+    let stats = prog.execute_with(&mut &b""[..], &mut Vec::new()).unwrap();
+
+    assert_eq!(
+        stats,
+        RunStats {
+            steps: 16,
+            output_bytes: 2,
+            final_ptr: 1,
+            max_ptr: 1,
+            max_value: 2,
+            halted: false,
+        }
+    );
+}
+
+#[test]
+fn run_stats_keep_the_furthest_cell_and_biggest_value() {
+    let mut prog = Program::new();
+
+    prog.compile("+++>>>>++<<<<-").unwrap();
+
+    let stats = prog.execute_with(&mut &b""[..], &mut Vec::new()).unwrap();
+
+    assert_eq!((stats.final_ptr, stats.max_ptr, stats.max_value), (0, 4, 3));
+}
+
+#[test]
+fn a_snapshot_resumes_where_the_run_stopped() {
+    let source = "++++++++[>++++++++<-]>+.+.+.";
+    let mut limited = Program::with_config(Config {
+        max_steps: Some(40),
+        ..Config::default()
+    });
+
+    limited.compile(source).unwrap();
+
+    let mut output = Vec::new();
+
+    assert!(matches!(
+        limited.execute_with(&mut &b""[..], &mut output),
+        Err(InterpreterError::StepLimitExceeded)
+    ));
+
+    let mut prog = Program::new();
+
+    prog.compile(source).unwrap();
+    prog.restore(limited.snapshot());
+    prog.resume_with(&mut &b""[..], &mut output).unwrap();
+
+    assert_eq!(output, b"ABC");
+}
+
+#[test]
+fn match_and_table_dispatch_run_the_same() {
+    let cases = [
+        (include_str!("../examples/hello.bf"), Config::default()),
+        (
+            include_str!("../examples/bottles.bf"),
+            Config {
+                optimize: Passes::ALL,
+                ..Config::default()
+            },
+        ),
+        (
+            ",[.,]>+[>]<<[<]",
+            Config {
+                optimize: Passes::ALL,
+                ..small_tape(PointerPolicy::Clamp)
+            },
+        ),
+        (",+[>+]", small_tape(PointerPolicy::Error)),
+        ("+[<-]", small_tape(PointerPolicy::Wrap)),
+        (
+            "+[>+]",
+            Config {
+                max_steps: Some(1000),
+                ..Config::default()
+            },
+        ),
+        (
+            "+[[[+]]]",
+            Config {
+                max_loop_depth: Some(2),
+                ..Config::default()
+            },
+        ),
+    ];
+
+    for (source, config) in cases {
+        let runs = [Dispatch::Match, Dispatch::Table].map(|dispatch| {
+            let mut prog = Program::with_config(Config { dispatch, ..config });
+            let mut output = Vec::new();
+
+            prog.compile(source).unwrap();
+
+            let result = prog.execute_with(&mut &b"abc"[..], &mut output);
+
+            (format!("{:?}", result), output, prog.snapshot())
+        });
+
+        assert_eq!(runs[0], runs[1], "{}", source);
+    }
+}
+
+#[test]
+fn wide_output_writes_every_byte_of_the_cell() {
+    let source = format!("{}.", "+".repeat(0x1234));
+    let output = |cell_width, wide_output, output_endian| {
+        let mut prog = Program::with_config(Config {
+            cell_width,
+            wide_output,
+            output_endian,
+            program_size: source.len(),
+            ..Config::default()
+        });
+
+        prog.compile(&source).unwrap();
+        prog.run_with_io(b"").unwrap()
+    };
+
+    assert_eq!(output(CellWidth::U16, false, Endian::Big), [0x34]);
+    assert_eq!(output(CellWidth::U16, true, Endian::Big), [0x12, 0x34]);
+    assert_eq!(output(CellWidth::U16, true, Endian::Little), [0x34, 0x12]);
+    assert_eq!(
+        output(CellWidth::U32, true, Endian::Big),
+        [0, 0, 0x12, 0x34]
+    );
+    assert_eq!(output(CellWidth::U8, true, Endian::Little), [0x34]);
+}
+
+#[test]
+fn wide_input_reads_the_cell_in_either_byte_order() {
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let read = |input_endian| {
+            let mut prog = Program::with_config(Config {
+                cell_width: CellWidth::U16,
+                wide_input: true,
+                input_endian,
+                dispatch,
+                ..Config::default()
+            });
+
+            prog.compile(",").unwrap();
+            prog.run_with_io(&[0x12, 0x34]).unwrap();
+            prog.tape()[0]
+        };
+
+        assert_eq!(read(Endian::Big), 0x1234);
+        assert_eq!(read(Endian::Little), 0x3412);
+    }
+}
+
+#[test]
+fn output_encodings_turn_cells_into_text() {
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let run = |output_encoding, code: u32| {
+            let mut prog = Program::with_config(Config {
+                cell_width: CellWidth::U32,
+                wide_input: true,
+                output_encoding,
+                dispatch,
+                ..Config::default()
+            });
+
+            prog.compile(",.").unwrap();
+            prog.run_with_io(&code.to_be_bytes()).unwrap()
+        };
+
+        assert_eq!(run(OutputEncoding::Bytes, 0x1f980), [0x80]);
+        assert_eq!(run(OutputEncoding::Utf8, 0x1f980), "🦀".as_bytes());
+        assert_eq!(run(OutputEncoding::Utf8, 0xe9), "é".as_bytes());
+        assert_eq!(run(OutputEncoding::Utf8, 0xd800), "\u{fffd}".as_bytes());
+        assert_eq!(run(OutputEncoding::Latin1, 0xe9), [0xe9]);
+        assert_eq!(run(OutputEncoding::Latin1, 0x1e9), b"?");
+    }
+}
+
+#[test]
+fn output_past_max_output_is_an_error() {
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let mut prog = Program::with_config(Config {
+            max_output: Some(3),
+            dispatch,
+            ..Config::default()
+        });
+
+        prog.compile("+...").unwrap();
+        assert_eq!(prog.run_with_io(b"").unwrap(), [1, 1, 1]);
+        prog.compile("+....").unwrap();
+        assert!(matches!(
+            prog.run_with_io(b""),
+            Err(InterpreterError::OutputLimitExceeded { pc: 4, bytes: 3 })
+        ));
+    }
+}
+
+#[test]
+fn setting_the_interrupt_stops_the_run() {
+    static INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let mut prog = Program::with_config(Config {
+            interrupt: Some(&INTERRUPT),
+            max_steps: Some(1000),
+            dispatch,
+            ..Config::default()
+        });
+
+        prog.compile("+[>+<]").unwrap();
+        INTERRUPT.store(false, Ordering::Relaxed);
+        assert!(matches!(
+            prog.run_with_io(b""),
+            Err(InterpreterError::StepLimitExceeded)
+        ));
+
+        INTERRUPT.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            prog.run_with_io(b""),
+            Err(InterpreterError::Interrupted { steps: 0 })
+        ));
+    }
+}
+
+#[test]
+fn numeric_io_reads_and_writes_decimal() {
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let run = |input: &[u8], signed| {
+            let mut prog = Program::with_config(Config {
+                numeric_io: true,
+                signed,
+                dispatch,
+                ..Config::default()
+            });
+
+            prog.compile(",>,[-<+>]<.").unwrap();
+            prog.run_with_io(input)
+        };
+
+        assert_eq!(run(b"3 4", false).unwrap(), b"7 ");
+        assert_eq!(run(b"  12\n\t30\n", false).unwrap(), b"42 ");
+        assert_eq!(run(b"-3 250", true).unwrap(), b"-9 ");
+        assert_eq!(run(b"-3 250", false).unwrap(), b"247 ");
+        assert_eq!(run(b"300", false).unwrap(), b"44 ");
+        assert!(matches!(
+            run(b"3x 4", false),
+            Err(InterpreterError::FailedToRead { pc: 0 })
+        ));
+        assert!(matches!(
+            run(b"3 -", false),
+            Err(InterpreterError::FailedToRead { pc: 2 })
+        ));
+    }
+}
+
+#[test]
+fn a_program_can_take_its_input_from_memory() {
+    let sum = ",>,[-<+>]<";
+    let mut prog = Program::with_input(Config::default(), vec![3u8, 4]);
+
+    prog.compile(sum).unwrap();
+    prog.execute().unwrap();
+    assert_eq!(prog.tape()[0], 7);
+
+    // The bytes were used up, so the next run hits the end of the input.
+    prog.execute().unwrap();
+    assert_eq!(prog.tape()[0], 0);
+
+    let queue = Rc::new(RefCell::new(VecDeque::new()));
+    let mut prog = Program::with_input(Config::default(), Rc::clone(&queue));
+
+    prog.compile(sum).unwrap();
+
+    for (a, b) in [(1, 2), (40, 2)] {
+        queue.borrow_mut().extend([a, b]);
+        prog.execute().unwrap();
+        assert_eq!(prog.tape()[0], u32::from(a + b));
+    }
+
+    assert_eq!(queue.borrow_mut().read_byte(), None);
+}
+
+#[test]
+fn stepping_to_the_end_matches_executing() {
+    // Reads, clears and scans, once optimized.
+    let source = ">+>+>+>,[-<+>]<[<]>.[-],[.,]";
+
+    for dispatch in [Dispatch::Match, Dispatch::Table] {
+        let config = Config {
+            optimize: Passes::ALL,
+            dispatch,
+            ..Config::default()
+        };
+        let mut executed = Program::with_input(config.clone(), b"ab".to_vec());
+
+        executed.compile(source).unwrap();
+        assert!(executed
+            .instructions()
+            .iter()
+            .any(|instruction| instruction.operator == Op::ScanLeft));
+
+        let mut expected = Vec::new();
+
+        executed
+            .execute_with(&mut &b"ab"[..], &mut expected)
+            .unwrap();
+
+        let mut stepped = Program::with_input(config, b"ab".to_vec());
+        let mut output = Vec::new();
+
+        stepped.compile(source).unwrap();
+
+        loop {
+            let result = stepped.step().unwrap();
+
+            output.extend(result.output);
+
+            if result.ended {
+                break;
+            }
+        }
+
+        assert_eq!(output, expected, "{:?}", dispatch);
+        assert_eq!(output, [1, b'b']);
+        assert_eq!(stepped.snapshot(), executed.snapshot(), "{:?}", dispatch);
+    }
+}
+
+#[test]
+fn stepping_runs_one_instruction_at_a_time() {
+    let source = "++.>,[-<+>]<.";
+    let mut prog = Program::with_input(Config::default(), vec![3u8]);
+
+    prog.compile(source).unwrap();
+
+    let first = prog.step().unwrap();
+    assert_eq!(
+        first,
+        StepResult {
+            ended: false,
+            pc: 1,
+            output: Vec::new()
+        }
+    );
+    assert_eq!(prog.tape()[0], 1);
+
+    prog.step().unwrap();
+    let saved = prog.snapshot();
+    assert_eq!(prog.step().unwrap().output, [2]);
+
+    // Going back a step and taking it again writes the same byte.
+    prog.restore(saved);
+    assert_eq!(prog.step().unwrap().output, [2]);
+
+    let mut output = vec![2];
+    let mut steps = 3;
+
+    loop {
+        let result = prog.step().unwrap();
+
+        output.extend(result.output);
+        steps += 1;
+
+        if result.ended {
+            break;
+        }
+    }
+
+    let mut expected = Program::new();
+    let mut expected_output = Vec::new();
+
+    expected.compile(source).unwrap();
+
+    let stats = expected
+        .execute_with(&mut &[3u8][..], &mut expected_output)
+        .unwrap();
+
+    assert_eq!(output, expected_output);
+    assert_eq!(steps, stats.steps);
+    assert!(prog.step().unwrap().ended);
+}
+
+#[test]
+fn breakpoints_stop_before_the_instruction() {
+    let source = "++[>+++<-]>.";
+    let stop = |break_at, break_when| {
+        let mut prog = Program::with_config(Config {
+            break_at,
+            break_when,
+            break_exit: true,
+            ..Config::default()
+        });
+
+        prog.compile(source).unwrap();
+
+        let result = prog.run_with_io(b"");
+
+        (result, prog.tape()[0], prog.tape()[1])
+    };
+
+    assert_eq!(
+        Condition::parse(" cell1 >= 5"),
+        Some(Condition {
+            cell: 1,
+            comparison: Comparison::GreaterOrEqual,
+            value: 5
+        })
+    );
+    assert_eq!(Condition::parse("cell1 = 5"), None);
+    assert_eq!(Condition::parse("ptr==5"), None);
+
+    assert!(matches!(
+        stop(Some(8), None),
+        (Err(InterpreterError::Breakpoint { pc: 8 }), 2, 3)
+    ));
+    assert!(matches!(
+        stop(None, Condition::parse("cell1>=5")),
+        (Err(InterpreterError::Breakpoint { pc: 6 }), 1, 5)
+    ));
+    assert_eq!(stop(None, Condition::parse("cell1==7")).0.unwrap(), [6]);
+
+    // A cell past the end of the tape so far already holds the fill value.
+    let mut prog = Program::with_config(Config {
+        init_cell: 1,
+        tape_size: 2,
+        break_when: Condition::parse("cell5==1"),
+        break_exit: true,
+        ..Config::default()
+    });
+
+    prog.compile("+").unwrap();
+    assert!(matches!(
+        prog.run_with_io(b""),
+        Err(InterpreterError::Breakpoint { pc: 0 })
+    ));
+}
+
+#[test]
+fn at_halts_the_run_with_debug_ops() {
+    for dispatch in [Dispatch::Table, Dispatch::Match] {
+        let run = |debug_ops| {
+            let mut prog = Program::with_config(Config {
+                debug_ops,
+                dispatch,
+                ..Config::default()
+            });
+            let mut output = Vec::new();
+
+            prog.compile("+++.@+++.").unwrap();
+
+            let stats = prog.execute_with(&mut &b""[..], &mut output).unwrap();
+
+            (output, stats.halted, prog.pc())
+        };
+
+        assert_eq!(run(true), (vec![3], true, 4));
+        assert_eq!(run(false), (vec![3, 6], false, 8));
+    }
+}
@@ -0,0 +1,70 @@
+#[macro_use]
+mod common;
+
+use mindsuck::{Config, EofMode};
+
+// Daniel B. Cristofani's bubble sort, sorts its input up to the first NUL.
+const BUBBLE_SORT: &str = ">>,[>>,]<<[[<<]>>>>[<<[>+<<+>-]>>[>+<<<<[->]>[<]>>-]<<<[[-]>>[>+<-]>>[<<<+>>>-]]>>[[<+>-]>>]<]<<[>>+<<-]<<]>>>>[.>>]";
+
+#[test]
+fn hello_world() {
+    assert_bf!(include_str!("../examples/hello.bf"), b"", b"Hello World!\n");
+}
+
+#[test]
+fn cat() {
+    assert_bf!(",[.,]", b"meow\n", b"meow\n");
+    assert_bf!(",[.,]", b"", b"");
+}
+
+#[test]
+fn rot13() {
+    const ROT13: &str = include_str!("../examples/rot13.bf");
+
+    // Stops on the -1 or untouched cell `,` leaves at the end of the input.
+    for eof in [EofMode::NegativeOne, EofMode::Unchanged] {
+        let config = Config {
+            eof,
+            ..Config::default()
+        };
+
+        assert_bf!(
+            ROT13,
+            b"Hello, World!\n",
+            b"Uryyb, Jbeyq!\n",
+            config.clone()
+        );
+        assert_bf!(ROT13, b"xyz ABC 123", b"klm NOP 123", config);
+    }
+}
+
+#[test]
+fn bubble_sort() {
+    assert_bf!(BUBBLE_SORT, b"sorting", b"ginorst");
+    assert_bf!(BUBBLE_SORT, b"9182736450", b"0123456789");
+    assert_bf!(BUBBLE_SORT, b"", b"");
+}
+
+#[test]
+fn brainfuck_in_brainfuck() {
+    const INTERPRETER: &str = include_str!("../examples/interpreter.bf");
+
+    // The interpreted program comes first, then a `!`, then its own input.
+    let hello = [include_str!("../examples/hello.bf").as_bytes(), b"!"].concat();
+    let rot13 = [
+        include_str!("../examples/rot13.bf").as_bytes(),
+        b"!Hello, World!\n",
+    ]
+    .concat();
+
+    assert_bf!(INTERPRETER, &hello, b"Hello World!\n");
+    assert_bf!(
+        INTERPRETER,
+        &rot13,
+        b"Uryyb, Jbeyq!\n",
+        Config {
+            eof: EofMode::Unchanged,
+            ..Config::default()
+        }
+    );
+}